@@ -7,31 +7,57 @@ pub enum Action {
     Quit,
     RotateLeft,
     RotateRight,
+    Rotate180,
     ShiftLeft,
     ShiftRight,
     HardDrop,
     SoftDrop,
     Hold,
+    Pause,
 }
 
 impl Action {
     // Iterator over all available actions
     pub fn iterator() -> std::slice::Iter<'static, Action> {
-        static ACTIONS: [Action; 8] = [
+        static ACTIONS: [Action; 10] = [
             Action::Quit,
             Action::RotateLeft,
             Action::RotateRight,
+            Action::Rotate180,
             Action::ShiftLeft,
             Action::ShiftRight,
             Action::HardDrop,
             Action::SoftDrop,
             Action::Hold,
+            Action::Pause,
         ];
         ACTIONS.iter()
     }
 
-    // List of keys/combinations associated with an action
-    pub fn keys(&self) -> Vec<Key> {
+    // Name of the action in the config file
+    pub fn config_name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::RotateLeft => "rotate_left",
+            Action::RotateRight => "rotate_right",
+            Action::Rotate180 => "rotate_180",
+            Action::ShiftLeft => "shift_left",
+            Action::ShiftRight => "shift_right",
+            Action::HardDrop => "hard_drop",
+            Action::SoftDrop => "soft_drop",
+            Action::Hold => "hold",
+            Action::Pause => "pause",
+        }
+    }
+
+    pub fn from_config_name(name: &str) -> Option<Action> {
+        Action::iterator()
+            .find(|action| action.config_name() == name)
+            .copied()
+    }
+
+    // Default list of keys/combinations associated with an action, used when the config doesn't say otherwise
+    pub fn default_keys(&self) -> Vec<Key> {
         match self {
             Action::Quit => vec![Key::Ctrl(BaseKey::Char('c'))],
             Action::ShiftLeft => vec![Key::Plain(BaseKey::Left), Key::Plain(BaseKey::Char('a'))],
@@ -41,9 +67,11 @@ impl Action {
                 Key::Plain(BaseKey::Char(',')),
             ],
             Action::RotateRight => vec![Key::Plain(BaseKey::Char('x')), Key::Plain(BaseKey::Up)],
+            Action::Rotate180 => vec![Key::Plain(BaseKey::Char('v'))],
             Action::HardDrop => vec![Key::Plain(BaseKey::Char(' '))],
             Action::SoftDrop => vec![Key::Plain(BaseKey::Down)],
             Action::Hold => vec![Key::Plain(BaseKey::Char('c'))],
+            Action::Pause => vec![Key::Plain(BaseKey::Esc), Key::Plain(BaseKey::Char('p'))],
         }
     }
 }
@@ -54,32 +82,68 @@ impl Display for Action {
             Action::Quit => write!(f, "Quit"),
             Action::RotateLeft => write!(f, "Rotate Left"),
             Action::RotateRight => write!(f, "Rotate Right"),
+            Action::Rotate180 => write!(f, "Rotate 180"),
             Action::ShiftLeft => write!(f, "Move Left"),
             Action::ShiftRight => write!(f, "Move Right"),
             Action::HardDrop => write!(f, "Hard Drop"),
             Action::SoftDrop => write!(f, "Soft Drop"),
             Action::Hold => write!(f, "Hold"),
+            Action::Pause => write!(f, "Pause"),
         }
     }
 }
 
+// Key bindings for every action, an action can have any number of keys
 #[derive(Default, Debug, Clone)]
-pub struct Actions(Vec<Action>);
+pub struct Actions(Vec<(Action, Vec<Key>)>);
 
 impl Actions {
     // Find the associated action for a given input
     pub fn find(&self, key: Key) -> Option<&Action> {
-        for action in Action::iterator() {
-            if action.keys().contains(&key) {
-                return Some(action);
+        self.0
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| action)
+    }
+
+    // Keys bound to an action, in order of preference
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.0
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn bind(&mut self, action: Action, keys: Vec<Key>) {
+        match self.0.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, old_keys)) => *old_keys = keys,
+            None => self.0.push((action, keys)),
+        }
+    }
+
+    // Every key bound to more than one action, along with the first two actions it's bound to
+    pub fn conflicts(&self) -> Vec<(Key, Action, Action)> {
+        let mut conflicts = Vec::new();
+        for (i, (action, keys)) in self.0.iter().enumerate() {
+            for key in keys {
+                if let Some((other, _)) = self.0[i + 1..].iter().find(|(_, k)| k.contains(key)) {
+                    conflicts.push((*key, *action, *other));
+                }
             }
         }
-        None
+        conflicts
     }
 }
 
+// Default bindings for the given actions
 impl From<Vec<Action>> for Actions {
     fn from(actions: Vec<Action>) -> Self {
-        Self(actions)
+        Self(
+            actions
+                .into_iter()
+                .map(|action| (action, action.default_keys()))
+                .collect(),
+        )
     }
 }
@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::actions::{Action, Actions};
+use crate::{game::handling::Handling, inputs::keys::Key};
+
+// Layout of the config file, anything left out keeps its default
+//
+// [keys]
+// rotate_left = ["z", "Ctrl+Left"]
+// hard_drop = ["Space"]
+//
+// [handling]
+// das = 167
+// arr = 0
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    keys: BTreeMap<String, Vec<String>>,
+    handling: HandlingFile,
+}
+
+// DAS and ARR in milliseconds, and SDF as a multiple of gravity, zero meaning instant for all of them
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct HandlingFile {
+    das: u64,
+    arr: u64,
+    sdf: u32,
+}
+
+impl Default for HandlingFile {
+    fn default() -> Self {
+        Self::from(Handling::default())
+    }
+}
+
+impl From<Handling> for HandlingFile {
+    fn from(handling: Handling) -> Self {
+        HandlingFile {
+            das: handling.das.as_millis() as u64,
+            arr: handling.arr.as_millis() as u64,
+            sdf: handling.sdf,
+        }
+    }
+}
+
+impl From<HandlingFile> for Handling {
+    fn from(file: HandlingFile) -> Self {
+        Handling {
+            das: Duration::from_millis(file.das),
+            arr: Duration::from_millis(file.arr),
+            sdf: file.sdf,
+        }
+    }
+}
+
+// User settings loaded from the config file
+pub struct Config {
+    pub actions: Actions,
+    pub handling: Handling,
+    // Where the settings are saved back to when changed in game, None if there's nowhere to put them
+    pub path: Option<PathBuf>,
+    // Where replays and records are written, None if there's nowhere to put them
+    pub data_dir: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Write(PathBuf, io::Error),
+    NoPath,
+    Parse(PathBuf, toml::de::Error),
+    UnknownAction(String),
+    SdfTooHigh(u32),
+    InvalidKey {
+        action: Action,
+        key: String,
+    },
+    Conflict {
+        key: Key,
+        first: Action,
+        second: Action,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "couldn't read {}: {err}", path.display()),
+            ConfigError::Write(path, err) => write!(f, "couldn't write {}: {err}", path.display()),
+            ConfigError::NoPath => write!(f, "no config directory to save to"),
+            ConfigError::Parse(path, err) => write!(f, "couldn't parse {}: {err}", path.display()),
+            ConfigError::UnknownAction(name) => write!(f, "unknown action \"{name}\" in [keys]"),
+            ConfigError::SdfTooHigh(sdf) => write!(
+                f,
+                "sdf {sdf} in [handling] is above the maximum of {}",
+                Handling::MAX_SDF
+            ),
+            ConfigError::InvalidKey { action, key } => {
+                write!(f, "invalid key \"{key}\" for {}", action.config_name())
+            }
+            ConfigError::Conflict { key, first, second } => write!(
+                f,
+                "\"{}\" is bound to both {} and {}",
+                key.config_name(),
+                first.config_name(),
+                second.config_name()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // Where the config file lives when no other path is given, $XDG_CONFIG_HOME/termtris/config.toml on Linux
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("termtris").join("config.toml"))
+    }
+
+    // Where replays and records go when no other directory is given, $XDG_DATA_HOME/termtris on Linux
+    pub fn default_data_dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("termtris"))
+    }
+
+    pub fn default_actions() -> Actions {
+        Actions::from(Action::iterator().cloned().collect::<Vec<_>>())
+    }
+
+    pub fn defaults(path: Option<PathBuf>) -> Self {
+        Config {
+            actions: Self::default_actions(),
+            handling: Handling::default(),
+            path,
+            data_dir: Self::default_data_dir(),
+        }
+    }
+
+    // Load the config from the default path, a missing file just means the defaults are used
+    pub fn load_default() -> Result<Self, ConfigError> {
+        match Self::default_path() {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::defaults(None)),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::defaults(Some(path.to_path_buf())))
+            }
+            Err(err) => return Err(ConfigError::Io(path.to_path_buf(), err)),
+        };
+        let file: ConfigFile =
+            toml::from_str(&contents).map_err(|err| ConfigError::Parse(path.to_path_buf(), err))?;
+
+        if file.handling.sdf > Handling::MAX_SDF {
+            return Err(ConfigError::SdfTooHigh(file.handling.sdf));
+        }
+        let mut config = Self::defaults(Some(path.to_path_buf()));
+        config.handling = Handling::from(file.handling);
+        for (name, keys) in file.keys {
+            let action = Action::from_config_name(&name).ok_or(ConfigError::UnknownAction(name))?;
+            let keys = keys
+                .into_iter()
+                .map(|key| {
+                    key.parse()
+                        .map_err(|_| ConfigError::InvalidKey { action, key })
+                })
+                .collect::<Result<Vec<Key>, _>>()?;
+            config.actions.bind(action, keys);
+        }
+
+        if let Some(&(key, first, second)) = config.actions.conflicts().first() {
+            return Err(ConfigError::Conflict { key, first, second });
+        }
+
+        Ok(config)
+    }
+
+    // Write the settings back to where they were loaded from, creating the file if needed
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let path = self.path.as_ref().ok_or(ConfigError::NoPath)?;
+        let file = ConfigFile {
+            keys: Action::iterator()
+                .map(|action| {
+                    let keys = self.actions.keys(*action).iter();
+                    (
+                        action.config_name().to_string(),
+                        keys.map(|key| key.config_name()).collect(),
+                    )
+                })
+                .collect(),
+            handling: HandlingFile::from(self.handling),
+        };
+        // Serializing plain maps and numbers can't fail
+        let contents = toml::to_string(&file).unwrap();
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| ConfigError::Write(path.clone(), err))?;
+        }
+        fs::write(path, contents).map_err(|err| ConfigError::Write(path.clone(), err))
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt::{self, Display};
+
+// Entries of the main menu, in the order they're shown
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MenuItem {
+    Marathon,
+    Sprint,
+    Ultra,
+    Settings,
+    Quit,
+}
+
+impl MenuItem {
+    pub fn iterator() -> std::slice::Iter<'static, MenuItem> {
+        static ITEMS: [MenuItem; 5] = [
+            MenuItem::Marathon,
+            MenuItem::Sprint,
+            MenuItem::Ultra,
+            MenuItem::Settings,
+            MenuItem::Quit,
+        ];
+        ITEMS.iter()
+    }
+}
+
+impl Display for MenuItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MenuItem::Marathon => write!(f, "Marathon"),
+            MenuItem::Sprint => write!(f, "Sprint"),
+            MenuItem::Ultra => write!(f, "Ultra"),
+            MenuItem::Settings => write!(f, "Settings"),
+            MenuItem::Quit => write!(f, "Quit"),
+        }
+    }
+}
+
+// Entries of the pause menu, in the order they're shown
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PauseItem {
+    Resume,
+    Restart,
+    MainMenu,
+}
+
+impl PauseItem {
+    pub fn iterator() -> std::slice::Iter<'static, PauseItem> {
+        static ITEMS: [PauseItem; 3] = [PauseItem::Resume, PauseItem::Restart, PauseItem::MainMenu];
+        ITEMS.iter()
+    }
+}
+
+impl Display for PauseItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PauseItem::Resume => write!(f, "Resume"),
+            PauseItem::Restart => write!(f, "Restart"),
+            PauseItem::MainMenu => write!(f, "Main Menu"),
+        }
+    }
+}
+
+// Rows of the settings screen, in the order they're shown
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Setting {
+    Randomizer,
+    Rotation,
+    Spins,
+    LockReset,
+    MarathonLevel,
+    SprintLines,
+    UltraDuration,
+    Das,
+    Arr,
+    Sdf,
+    Controls,
+}
+
+impl Setting {
+    pub fn iterator() -> std::slice::Iter<'static, Setting> {
+        static SETTINGS: [Setting; 11] = [
+            Setting::Randomizer,
+            Setting::Rotation,
+            Setting::Spins,
+            Setting::LockReset,
+            Setting::MarathonLevel,
+            Setting::SprintLines,
+            Setting::UltraDuration,
+            Setting::Das,
+            Setting::Arr,
+            Setting::Sdf,
+            Setting::Controls,
+        ];
+        SETTINGS.iter()
+    }
+}
+
+impl Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Setting::Randomizer => write!(f, "Randomizer"),
+            Setting::Rotation => write!(f, "Rotation"),
+            Setting::Spins => write!(f, "Spins"),
+            Setting::LockReset => write!(f, "Lock Reset"),
+            Setting::MarathonLevel => write!(f, "Marathon Level"),
+            Setting::SprintLines => write!(f, "Sprint Lines"),
+            Setting::UltraDuration => write!(f, "Ultra Time"),
+            Setting::Das => write!(f, "DAS"),
+            Setting::Arr => write!(f, "ARR"),
+            Setting::Sdf => write!(f, "Soft Drop"),
+            Setting::Controls => write!(f, "Controls"),
+        }
+    }
+}
+
+// Moves a menu selection up or down by one, wrapping around at the ends
+pub fn step(selected: usize, len: usize, down: bool) -> usize {
+    if down {
+        (selected + 1) % len
+    } else {
+        (selected + len - 1) % len
+    }
+}
+
+// Moves to the next or previous option in a list of choices, wrapping around at the ends
+pub fn cycle<T: PartialEq + Copy>(options: &[T], current: T, forward: bool) -> T {
+    let idx = options.iter().position(|&o| o == current).unwrap_or(0);
+    options[step(idx, options.len(), forward)]
+}
@@ -1,95 +1,567 @@
 pub mod actions;
+pub mod config;
+pub mod menu;
+pub mod playback;
+pub mod records;
 pub mod state;
 pub mod ui;
 
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use self::{
     actions::{Action, Actions},
-    state::AppState,
+    config::Config,
+    menu::{MenuItem, PauseItem, Setting},
+    playback::Playback,
+    records::Records,
+    state::{AppState, Rebind, Results},
 };
 
-use crate::{game::Game, inputs::keys::Key};
+use crate::{
+    game::{
+        clock::SystemClock,
+        config::GameConfig,
+        handling::HeldInput,
+        level::Level,
+        lock::LockReset,
+        mode::{GameMode, Outcome},
+        randomizer::RandomizerKind,
+        replay::Replay,
+        rotation::RotationKind,
+        score::SpinRule,
+        Game,
+    },
+    inputs::{
+        keys::{BaseKey, Key},
+        KeyState,
+    },
+};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum AppReturn {
     Exit,
     Continue,
-    UpdateSpeed,
 }
 
 // Struct to store the overall app state and process input events
-// State is pretty much unused for now, but planned for use when pause/menus are added
-pub struct App<'a> {
-    actions: Actions,
-    _state: AppState,
-    game: Game<'a>,
+pub struct App {
+    settings: Config,
+    state: AppState,
+    // Options every new game is started with, the mode is filled in when one is picked
+    config: GameConfig,
+    sprint_lines: u32,
+    ultra_duration: Duration,
+    game: Game,
+    records: Records,
+    // Set once the terminal sends a key release, until then held inputs can't be tracked
+    reports_releases: bool,
 }
 
-// I can't think of a reason to have a Default impl for any structs in this project but I'm open to any reasons
-#[allow(clippy::new_without_default)]
-impl<'a> App<'a> {
-    pub fn new() -> Self {
-        let actions = Actions::from(Action::iterator().cloned().collect::<Vec<_>>());
-        let state = AppState::initialized();
-        let game = Game::new();
+impl App {
+    const SPRINT_LINES: [u32; 4] = [10, 20, 40, 100];
+    const ULTRA_SECS: [u64; 4] = [60, 120, 180, 300];
+    const DAS_MS: [u64; 12] = [0, 50, 83, 100, 117, 133, 150, 167, 183, 200, 250, 300];
+    const ARR_MS: [u64; 8] = [0, 10, 17, 33, 50, 67, 83, 100];
+    // Zero is an instant soft drop so it goes last
+    const SDFS: [u32; 7] = [1, 2, 5, 10, 20, 40, 0];
+
+    // Handling starts out as whatever the config file says, changes on the settings screen only last the session
+    pub fn new(mut config: GameConfig, settings: Config) -> Self {
+        config.handling = settings.handling;
+        let state = AppState::MainMenu { selected: 0 };
+        let game = Game::with_config(config.clone(), Box::new(SystemClock::new()));
+        let records = Records::new(settings.data_dir.as_deref());
         App {
-            actions,
-            _state: state,
+            settings,
+            state,
+            config,
+            sprint_lines: GameMode::SPRINT_LINES,
+            ultra_duration: GameMode::ULTRA_DURATION,
             game,
+            records,
+            reports_releases: false,
         }
     }
 
     pub fn actions(&self) -> &Actions {
-        &self.actions
+        &self.settings.actions
     }
 
-    pub fn get_tick_delay(&self) -> Duration {
-        self.game.score.level.get_tick_delay()
+    // Skip the menu and start playing the given mode straight away
+    pub fn start_game(&mut self, mode: GameMode) {
+        self.config.mode = mode;
+        self.restart();
     }
 
-    // Handle an input
-    pub fn do_action(&mut self, key: Key) -> AppReturn {
-        if let Some(action) = self.actions.find(key) {
-            match action {
-                Action::Quit => AppReturn::Exit,
-                Action::ShiftLeft => {
-                    self.game.move_left();
-                    AppReturn::Continue
+    // Play a recorded game back instead of starting a new one
+    pub fn watch(&mut self, replay: Replay) {
+        let (playback, game) = Playback::new(replay);
+        self.game = game;
+        self.state = AppState::Watching(playback);
+    }
+
+    // Handle an input, what it does depends on which screen is showing
+    pub fn do_action(&mut self, key: Key, state: KeyState) -> AppReturn {
+        // Modifier keys on their own come through with the kitty protocol but aren't bindable
+        if let Key::Plain(BaseKey::None) | Key::Ctrl(BaseKey::None) = key {
+            return AppReturn::Continue;
+        }
+
+        match state {
+            KeyState::Press => {}
+            // Held keys are repeated by the game, not the terminal, menus still scroll on repeats
+            KeyState::Repeat if matches!(self.state, AppState::Playing) => {
+                return AppReturn::Continue;
+            }
+            KeyState::Repeat => {}
+            // Releases go to the game whatever screen is showing so nothing stays held
+            KeyState::Release => {
+                self.reports_releases = true;
+                self.release_key(key);
+                return AppReturn::Continue;
+            }
+        }
+
+        // A key being captured for rebinding shouldn't do anything else, even quit
+        if let AppState::Controls {
+            selected,
+            rebinding: Some(rebind),
+            ..
+        } = self.state
+        {
+            self.rebind(key, selected, rebind);
+            return AppReturn::Continue;
+        }
+
+        if let Some(Action::Quit) = self.settings.actions.find(key) {
+            self.save_replay();
+            return AppReturn::Exit;
+        }
+
+        match self.state {
+            AppState::MainMenu { selected } => self.do_main_menu_action(key, selected),
+            AppState::Playing => {
+                // Catch the game up first so nothing counts after time has run out
+                self.game.update();
+                if self.game.outcome().is_none() {
+                    self.do_game_action(key);
+                }
+                self.check_outcome();
+                AppReturn::Continue
+            }
+            AppState::Paused { selected } => {
+                self.do_pause_action(key, selected);
+                AppReturn::Continue
+            }
+            AppState::GameOver(_) => self.do_game_over_action(key),
+            AppState::Watching(_) => self.do_watch_action(key),
+            AppState::Settings { selected } => {
+                self.do_settings_action(key, selected);
+                AppReturn::Continue
+            }
+            AppState::Controls { selected, .. } => {
+                self.do_controls_action(key, selected);
+                AppReturn::Continue
+            }
+        }
+    }
+
+    fn do_main_menu_action(&mut self, key: Key, selected: usize) -> AppReturn {
+        let len = MenuItem::iterator().len();
+        match key {
+            Key::Plain(BaseKey::Up) => {
+                self.state = AppState::MainMenu {
+                    selected: menu::step(selected, len, false),
+                }
+            }
+            Key::Plain(BaseKey::Down) => {
+                self.state = AppState::MainMenu {
+                    selected: menu::step(selected, len, true),
+                }
+            }
+            Key::Plain(BaseKey::Enter) => match MenuItem::iterator().nth(selected) {
+                Some(MenuItem::Marathon) => self.start_game(GameMode::Marathon),
+                Some(MenuItem::Sprint) => self.start_game(GameMode::Sprint {
+                    lines: self.sprint_lines,
+                }),
+                Some(MenuItem::Ultra) => self.start_game(GameMode::Ultra {
+                    duration: self.ultra_duration,
+                }),
+                Some(MenuItem::Settings) => self.state = AppState::Settings { selected: 0 },
+                Some(MenuItem::Quit) | None => return AppReturn::Exit,
+            },
+            Key::Plain(BaseKey::Esc) => return AppReturn::Exit,
+            _ => {}
+        }
+        AppReturn::Continue
+    }
+
+    fn do_pause_action(&mut self, key: Key, selected: usize) {
+        let len = PauseItem::iterator().len();
+        match (key, self.settings.actions.find(key)) {
+            (_, Some(Action::Pause)) => self.resume(),
+            (Key::Plain(BaseKey::Up), _) => {
+                self.state = AppState::Paused {
+                    selected: menu::step(selected, len, false),
                 }
-                Action::ShiftRight => {
-                    self.game.move_right();
-                    AppReturn::Continue
+            }
+            (Key::Plain(BaseKey::Down), _) => {
+                self.state = AppState::Paused {
+                    selected: menu::step(selected, len, true),
+                }
+            }
+            (Key::Plain(BaseKey::Enter), _) => match PauseItem::iterator().nth(selected) {
+                Some(PauseItem::Resume) | None => self.resume(),
+                Some(PauseItem::Restart) => self.restart(),
+                Some(PauseItem::MainMenu) => {
+                    self.save_replay();
+                    self.state = AppState::MainMenu { selected: 0 };
                 }
-                Action::RotateLeft => {
-                    self.game.rotate_left();
-                    AppReturn::Continue
+            },
+            _ => {}
+        }
+    }
+
+    fn do_game_over_action(&mut self, key: Key) -> AppReturn {
+        match key {
+            Key::Plain(BaseKey::Char('q')) => return AppReturn::Exit,
+            Key::Plain(BaseKey::Enter) | Key::Plain(BaseKey::Char('r')) => self.restart(),
+            Key::Plain(BaseKey::Esc) => self.state = AppState::MainMenu { selected: 0 },
+            _ => {}
+        }
+        AppReturn::Continue
+    }
+
+    fn do_watch_action(&mut self, key: Key) -> AppReturn {
+        let AppState::Watching(playback) = &mut self.state else {
+            return AppReturn::Continue;
+        };
+        match key {
+            Key::Plain(BaseKey::Char(' ')) => playback.toggle_pause(),
+            Key::Plain(BaseKey::Char('.')) => playback.step(&mut self.game),
+            Key::Plain(BaseKey::Left) => {
+                let piece = self.game.pieces().saturating_sub(1);
+                playback.seek(&mut self.game, piece);
+            }
+            Key::Plain(BaseKey::Right) => {
+                let piece = self.game.pieces() + 1;
+                playback.seek(&mut self.game, piece);
+            }
+            Key::Plain(BaseKey::Up) => playback.faster(),
+            Key::Plain(BaseKey::Down) => playback.slower(),
+            Key::Plain(BaseKey::Char(digit @ '0'..='9')) if playback.seek.len() < 5 => {
+                playback.seek.push(digit)
+            }
+            Key::Plain(BaseKey::Enter) => {
+                if let Ok(piece) = std::mem::take(&mut playback.seek).parse() {
+                    playback.seek(&mut self.game, piece);
                 }
-                Action::RotateRight => {
-                    self.game.rotate_right();
-                    AppReturn::Continue
+            }
+            Key::Plain(BaseKey::Esc) if !playback.seek.is_empty() => playback.seek.clear(),
+            Key::Plain(BaseKey::Esc) | Key::Plain(BaseKey::Char('q')) => return AppReturn::Exit,
+            _ => {}
+        }
+        AppReturn::Continue
+    }
+
+    fn do_settings_action(&mut self, key: Key, selected: usize) {
+        let len = Setting::iterator().len();
+        let setting = *Setting::iterator().nth(selected).unwrap();
+        match key {
+            Key::Plain(BaseKey::Up) => {
+                self.state = AppState::Settings {
+                    selected: menu::step(selected, len, false),
                 }
-                Action::HardDrop => {
-                    self.game.hard_drop();
-                    AppReturn::Continue
+            }
+            Key::Plain(BaseKey::Down) => {
+                self.state = AppState::Settings {
+                    selected: menu::step(selected, len, true),
                 }
-                Action::SoftDrop => {
-                    self.game.soft_drop();
-                    AppReturn::Continue
+            }
+            Key::Plain(BaseKey::Enter) if setting == Setting::Controls => {
+                self.state = AppState::Controls {
+                    selected: 0,
+                    rebinding: None,
+                    message: None,
                 }
-                Action::Hold => {
-                    self.game.hold();
-                    AppReturn::Continue
+            }
+            Key::Plain(BaseKey::Left) => self.change_setting(setting, false),
+            Key::Plain(BaseKey::Right) | Key::Plain(BaseKey::Enter) => {
+                self.change_setting(setting, true)
+            }
+            Key::Plain(BaseKey::Esc) => self.state = AppState::MainMenu { selected: 0 },
+            _ => {}
+        }
+    }
+
+    fn change_setting(&mut self, setting: Setting, forward: bool) {
+        match setting {
+            Setting::Randomizer => {
+                let kinds: Vec<RandomizerKind> = RandomizerKind::iterator().copied().collect();
+                self.config.randomizer = menu::cycle(&kinds, self.config.randomizer, forward);
+            }
+            Setting::Rotation => {
+                let kinds: Vec<RotationKind> = RotationKind::iterator().copied().collect();
+                self.config.rotation = menu::cycle(&kinds, self.config.rotation, forward);
+            }
+            Setting::Spins => {
+                let rules: Vec<SpinRule> = SpinRule::iterator().copied().collect();
+                self.config.spin_rule = menu::cycle(&rules, self.config.spin_rule, forward);
+            }
+            Setting::LockReset => {
+                let policies: Vec<LockReset> = LockReset::iterator().copied().collect();
+                self.config.lock_reset = menu::cycle(&policies, self.config.lock_reset, forward);
+            }
+            Setting::MarathonLevel => {
+                let levels: Vec<u8> = (1..=Level::MAX).collect();
+                self.config.level = menu::cycle(&levels, self.config.level, forward);
+            }
+            Setting::SprintLines => {
+                self.sprint_lines = menu::cycle(&Self::SPRINT_LINES, self.sprint_lines, forward);
+            }
+            Setting::UltraDuration => {
+                let secs = menu::cycle(&Self::ULTRA_SECS, self.ultra_duration.as_secs(), forward);
+                self.ultra_duration = Duration::from_secs(secs);
+            }
+            Setting::Das => {
+                let das = self.config.handling.das.as_millis() as u64;
+                let das = menu::cycle(&Self::DAS_MS, das, forward);
+                self.config.handling.das = Duration::from_millis(das);
+            }
+            Setting::Arr => {
+                let arr = self.config.handling.arr.as_millis() as u64;
+                let arr = menu::cycle(&Self::ARR_MS, arr, forward);
+                self.config.handling.arr = Duration::from_millis(arr);
+            }
+            Setting::Sdf => {
+                self.config.handling.sdf =
+                    menu::cycle(&Self::SDFS, self.config.handling.sdf, forward);
+            }
+            Setting::Controls => {}
+        }
+    }
+
+    // Current value of a setting, as shown on the settings screen
+    pub fn setting_value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::Randomizer => format!("{}", self.config.randomizer),
+            Setting::Rotation => format!("{}", self.config.rotation),
+            Setting::Spins => format!("{}", self.config.spin_rule),
+            Setting::LockReset => format!("{}", self.config.lock_reset),
+            Setting::MarathonLevel => format!("{}", self.config.level),
+            Setting::SprintLines => format!("{}", self.sprint_lines),
+            Setting::UltraDuration => format!("{}s", self.ultra_duration.as_secs()),
+            Setting::Das => format!("{}ms", self.config.handling.das.as_millis()),
+            Setting::Arr => format!("{}ms", self.config.handling.arr.as_millis()),
+            Setting::Sdf => match self.config.handling.sdf {
+                0 => "Instant".to_string(),
+                sdf => format!("{sdf}x"),
+            },
+            Setting::Controls => String::new(),
+        }
+    }
+
+    fn do_controls_action(&mut self, key: Key, selected: usize) {
+        let len = Action::iterator().len();
+        let action = *Action::iterator().nth(selected).unwrap();
+        let (selected, rebinding, message) = match key {
+            Key::Plain(BaseKey::Up) => (menu::step(selected, len, false), None, None),
+            Key::Plain(BaseKey::Down) => (menu::step(selected, len, true), None, None),
+            Key::Plain(BaseKey::Enter) => (selected, Some(Rebind::Replace), None),
+            Key::Plain(BaseKey::Char('a')) => (selected, Some(Rebind::Add), None),
+            Key::Plain(BaseKey::Char('r')) => {
+                let mut actions = self.settings.actions.clone();
+                actions.bind(action, action.default_keys());
+                let message = match actions.conflicts().first() {
+                    Some((key, first, second)) => {
+                        let other = if *first == action { second } else { first };
+                        format!("Can't reset, {key} is bound to {other}")
+                    }
+                    None => self.save_actions(actions),
+                };
+                (selected, None, Some(message))
+            }
+            Key::Plain(BaseKey::Char('R')) => {
+                let message = self.save_actions(Config::default_actions());
+                (selected, None, Some(message))
+            }
+            Key::Plain(BaseKey::Esc) => {
+                self.state = AppState::Settings { selected: 0 };
+                return;
+            }
+            _ => return,
+        };
+        self.state = AppState::Controls {
+            selected,
+            rebinding,
+            message,
+        };
+    }
+
+    // Bind a captured key to the selected action, unless another action already uses it
+    fn rebind(&mut self, key: Key, selected: usize, rebind: Rebind) {
+        let action = *Action::iterator().nth(selected).unwrap();
+        let message = match self.settings.actions.find(key) {
+            // Esc cancels, unless the action is already bound to it
+            _ if key == Key::Plain(BaseKey::Esc) && !self.actions().keys(action).contains(&key) => {
+                None
+            }
+            Some(other) if *other != action => Some(format!("{key} is already bound to {other}")),
+            _ => {
+                let mut actions = self.settings.actions.clone();
+                let keys = match rebind {
+                    Rebind::Replace => vec![key],
+                    Rebind::Add => {
+                        let mut keys = actions.keys(action).to_vec();
+                        if !keys.contains(&key) {
+                            keys.push(key);
+                        }
+                        keys
+                    }
+                };
+                actions.bind(action, keys);
+                Some(self.save_actions(actions))
+            }
+        };
+        self.state = AppState::Controls {
+            selected,
+            rebinding: None,
+            message,
+        };
+    }
+
+    // Use the given bindings from now on and write them to the config file, returns a message saying how it went
+    fn save_actions(&mut self, actions: Actions) -> String {
+        self.settings.actions = actions;
+        match self.settings.save() {
+            Ok(()) => "Saved".to_string(),
+            Err(err) => format!("Not saved: {err}"),
+        }
+    }
+
+    fn do_game_action(&mut self, key: Key) {
+        if let Some(action) = self.settings.actions.find(key) {
+            match action {
+                Action::Quit => {}
+                Action::ShiftLeft => self.hold(HeldInput::Left),
+                Action::ShiftRight => self.hold(HeldInput::Right),
+                Action::RotateLeft => self.game.rotate_left(),
+                Action::RotateRight => self.game.rotate_right(),
+                Action::Rotate180 => self.game.rotate_180(),
+                Action::HardDrop => self.game.hard_drop(),
+                Action::SoftDrop => self.hold(HeldInput::SoftDrop),
+                Action::Hold => self.game.hold(),
+                Action::Pause => {
+                    self.game.pause();
+                    self.state = AppState::Paused { selected: 0 };
                 }
             }
-        } else {
-            AppReturn::Continue
         }
     }
 
-    // Handle a tick
+    // Without release events there's no telling how long a key is held, so every press is a tap
+    fn hold(&mut self, input: HeldInput) {
+        self.game.press(input);
+        if !self.reports_releases {
+            self.game.release(input);
+        }
+    }
+
+    fn release_key(&mut self, key: Key) {
+        let input = match self.settings.actions.find(key) {
+            Some(Action::ShiftLeft) => HeldInput::Left,
+            Some(Action::ShiftRight) => HeldInput::Right,
+            Some(Action::SoftDrop) => HeldInput::SoftDrop,
+            _ => return,
+        };
+        // A replay's game only takes the recorded inputs
+        if let AppState::Watching(_) = self.state {
+            return;
+        }
+        // Catch up first so any repeats due before the key came up still happen
+        if let AppState::Playing = self.state {
+            self.game.update();
+        }
+        self.game.release(input);
+    }
+
+    // Handle a tick, the game works out how much time has passed on its own
+    // Ticks are ignored on every screen but the game itself, so nothing moves while paused
     pub fn update_on_tick(&mut self) -> AppReturn {
-        self.game.move_down();
-        AppReturn::UpdateSpeed
+        match &mut self.state {
+            AppState::Playing => {
+                self.game.update();
+                self.check_outcome();
+            }
+            AppState::Watching(playback) => playback.update(&mut self.game),
+            _ => {}
+        }
+        AppReturn::Continue
+    }
+
+    // Save any new personal best once the game is over and switch to the game over screen
+    fn check_outcome(&mut self) {
+        let outcome = match self.game.outcome() {
+            Some(outcome) => outcome,
+            None => return,
+        };
+
+        let score = self.game.score.score();
+        let time = self.game.elapsed();
+        let new_best = match (self.game.mode(), outcome) {
+            (GameMode::Marathon, _) => self.records.submit_score(self.game.start_level(), score),
+            (GameMode::Sprint { lines }, Outcome::GoalReached) => {
+                self.records.submit_sprint(lines, time)
+            }
+            (GameMode::Ultra { duration }, Outcome::TimeUp) => {
+                self.records.submit_ultra(duration, score)
+            }
+            _ => false,
+        };
+        self.save_replay();
+
+        self.state = AppState::GameOver(Results {
+            mode: self.game.mode(),
+            outcome,
+            score,
+            lines: self.game.score.cleared_lines(),
+            level: self.game.score.level(),
+            pieces: self.game.pieces(),
+            perfect_clears: self.game.score.perfect_clears(),
+            time,
+            new_best,
+        });
+    }
+
+    fn resume(&mut self) {
+        self.game.resume();
+        self.state = AppState::Playing;
+    }
+
+    // Write the current game to the replays directory, named after when it was saved and its mode
+    // A replay that can't be written shouldn't get in the way of playing, so errors are ignored
+    fn save_replay(&mut self) {
+        if let AppState::Watching(_) = self.state {
+            return;
+        }
+        let (Some(dir), Some(replay)) = (&self.settings.data_dir, self.game.take_replay()) else {
+            return;
+        };
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let mode = match replay.config.mode {
+            GameMode::Marathon => "marathon".to_string(),
+            GameMode::Sprint { lines } => format!("sprint{lines}"),
+            GameMode::Ultra { duration } => format!("ultra{}", duration.as_secs()),
+        };
+        let _ = replay.save(&dir.join("replays"), &format!("{millis}-{mode}"));
+    }
+
+    fn restart(&mut self) {
+        self.save_replay();
+        self.game = Game::with_config(self.config.clone(), Box::new(SystemClock::new()));
+        self.state = AppState::Playing;
     }
 }
@@ -0,0 +1,124 @@
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::game::{
+    clock::{Clock, ManualClock},
+    replay::{Input, Replay},
+    Game,
+};
+
+// Plays a recorded game back through the engine, at whatever speed and from wherever the viewer likes
+// The game itself is kept by the app so it's drawn the same way as one being played
+#[derive(Clone)]
+pub struct Playback {
+    replay: Rc<Replay>,
+    clock: ManualClock,
+    // Index of the next input to be fed to the game
+    next: usize,
+    // How far into the replay playback has got, the game's clock only moves up to the last input fed to it
+    time: Duration,
+    speed: usize,
+    paused: bool,
+    last_update: Instant,
+    // Piece number typed in so far to seek to
+    pub seek: String,
+}
+
+impl Playback {
+    const SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+    // Start playing the replay from the beginning, along with the game it plays out on
+    pub fn new(replay: Replay) -> (Self, Game) {
+        let mut playback = Playback {
+            replay: Rc::new(replay),
+            clock: ManualClock::new(),
+            next: 0,
+            time: Duration::ZERO,
+            speed: 2,
+            paused: false,
+            last_update: Instant::now(),
+            seek: String::new(),
+        };
+        let game = playback.restart();
+        (playback, game)
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn speed(&self) -> f64 {
+        Self::SPEEDS[self.speed]
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Whether every input has been played
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.inputs.len()
+    }
+
+    // A fresh game for the replay, with playback back at the start
+    fn restart(&mut self) -> Game {
+        self.clock = ManualClock::new();
+        self.next = 0;
+        self.time = Duration::ZERO;
+        Game::with_config(self.replay.config.clone(), Box::new(self.clock.clone()))
+    }
+
+    // Move playback along by however much real time has passed, scaled by the speed
+    pub fn update(&mut self, game: &mut Game) {
+        let now = Instant::now();
+        if !self.paused {
+            self.time += (now - self.last_update).mul_f64(self.speed());
+            while matches!(self.replay.inputs.get(self.next), Some(&(at, _)) if at <= self.time) {
+                self.play_next(game);
+            }
+            self.paused = self.is_finished();
+        }
+        self.last_update = now;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused && !self.is_finished();
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(Self::SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    // Pause and play up to and including the next tick
+    pub fn step(&mut self, game: &mut Game) {
+        self.paused = true;
+        while !self.is_finished() && !self.play_next(game) {}
+        self.time = self.clock.now();
+    }
+
+    // Jump to the moment the given number of pieces have been locked, starting over to go backwards
+    pub fn seek(&mut self, game: &mut Game, piece: u32) {
+        if piece < game.pieces() {
+            *game = self.restart();
+        }
+        while !self.is_finished() && game.pieces() < piece && game.outcome().is_none() {
+            self.play_next(game);
+        }
+        self.time = self.clock.now();
+    }
+
+    // Feed the next input to the game at the time it was recorded, returns whether it was a tick
+    fn play_next(&mut self, game: &mut Game) -> bool {
+        let (at, input) = self.replay.inputs[self.next];
+        self.clock.set(at);
+        game.apply(input);
+        self.next += 1;
+        input == Input::Tick
+    }
+}
@@ -0,0 +1,102 @@
+use microkv::MicroKV;
+use std::{fs, path::Path, time::Duration};
+
+use crate::app::config::Config;
+
+// Struct to store personal bests for every mode, kept on disk between sessions
+pub struct Records {
+    db: MicroKV,
+}
+
+impl Records {
+    const NAME: &'static str = "score.data";
+
+    // Records are kept in the data directory, or in microkv's own directory if there isn't one
+    pub fn new(data_dir: Option<&Path>) -> Self {
+        let db = match data_dir {
+            Some(dir) => {
+                Self::move_old_records(dir);
+                MicroKV::open_with_base_path(Self::NAME, dir.to_path_buf())
+            }
+            None => MicroKV::open(Self::NAME),
+        };
+        let db = db.expect("Failed to create MicroKV").set_auto_commit(true);
+        Records { db }
+    }
+
+    // Records used to always be kept in microkv's directory, they're copied over the first time the default data
+    // directory is used so they aren't lost, other data directories start out empty
+    fn move_old_records(dir: &Path) {
+        let path = MicroKV::get_db_path_with_base_path(Self::NAME, dir.to_path_buf());
+        let old_path = MicroKV::get_db_path(Self::NAME);
+        if Config::default_data_dir().as_deref() == Some(dir)
+            && !path.exists()
+            && old_path.is_file()
+        {
+            let _ = fs::create_dir_all(dir).and_then(|_| fs::copy(old_path, path));
+        }
+    }
+
+    // Marathon high scores are kept separately for each starting level
+    // Level 1 keeps the key from before starting levels could be picked so old high scores carry over
+    fn score_key(level: u8) -> String {
+        match level {
+            1 => "score".to_string(),
+            level => format!("score_{level}"),
+        }
+    }
+
+    pub fn high_score(&self, level: u8) -> u32 {
+        match self.db.get(Self::score_key(level)) {
+            Ok(Some(score)) => score,
+            _ => 0,
+        }
+    }
+
+    // Saves the score if it beats the high score for its starting level, returns whether it did
+    pub fn submit_score(&mut self, level: u8, score: u32) -> bool {
+        if score <= self.high_score(level) {
+            return false;
+        }
+        self.db.put(Self::score_key(level), &score).unwrap();
+        true
+    }
+
+    // Best time for a sprint of the given number of lines, stored separately for each line count
+    pub fn sprint_best(&self, lines: u32) -> Option<Duration> {
+        match self.db.get::<u64>(format!("sprint_{lines}")) {
+            Ok(Some(millis)) => Some(Duration::from_millis(millis)),
+            _ => None,
+        }
+    }
+
+    // Saves the time if it beats the best for that line count, returns whether it did
+    pub fn submit_sprint(&mut self, lines: u32, time: Duration) -> bool {
+        if matches!(self.sprint_best(lines), Some(best) if best <= time) {
+            return false;
+        }
+        self.db
+            .put(format!("sprint_{lines}"), &(time.as_millis() as u64))
+            .unwrap();
+        true
+    }
+
+    // Best score for an ultra of the given length, kept apart from the marathon high score
+    pub fn ultra_best(&self, duration: Duration) -> u32 {
+        match self.db.get(format!("ultra_{}", duration.as_secs())) {
+            Ok(Some(score)) => score,
+            _ => 0,
+        }
+    }
+
+    // Saves the score if it beats the best for that length, returns whether it did
+    pub fn submit_ultra(&mut self, duration: Duration, score: u32) -> bool {
+        if score <= self.ultra_best(duration) {
+            return false;
+        }
+        self.db
+            .put(format!("ultra_{}", duration.as_secs()), &score)
+            .unwrap();
+        true
+    }
+}
@@ -1,15 +1,53 @@
 use std::time::Duration;
 
+use super::playback::Playback;
+use crate::game::mode::{GameMode, Outcome};
+
+// Which screen the app is on, along with anything that screen needs to remember
 #[derive(Clone)]
 pub enum AppState {
-    Init,
-    Initialized { duration: Duration },
+    MainMenu {
+        selected: usize,
+    },
+    Playing,
+    // The game's time is stopped and the board is hidden
+    Paused {
+        selected: usize,
+    },
+    // A finished game's results are shown until the player starts another or quits
+    GameOver(Results),
+    // A replay is played back on the game instead of the player's inputs
+    Watching(Playback),
+    Settings {
+        selected: usize,
+    },
+    // Key bindings for every action, with the next key press captured when rebinding
+    Controls {
+        selected: usize,
+        rebinding: Option<Rebind>,
+        message: Option<String>,
+    },
+}
+
+// What the next key press on the controls screen does to the selected action
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rebind {
+    // The key becomes the only one bound to the action
+    Replace,
+    // The key is bound alongside the ones the action already has
+    Add,
 }
 
-impl AppState {
-    pub fn initialized() -> Self {
-        Self::Initialized {
-            duration: Duration::from_secs(1),
-        }
-    }
+// Final stats of a finished game
+#[derive(Clone)]
+pub struct Results {
+    pub mode: GameMode,
+    pub outcome: Outcome,
+    pub score: u32,
+    pub lines: u32,
+    pub level: u8,
+    pub pieces: u32,
+    pub perfect_clears: u32,
+    pub time: Duration,
+    pub new_best: bool,
 }
@@ -1,18 +1,58 @@
-use crate::{app::App, game::Game};
+use std::time::Duration;
+
+use crate::{
+    app::{
+        menu::{MenuItem, PauseItem, Setting},
+        playback::Playback,
+        records::Records,
+        state::{AppState, Rebind, Results},
+        App,
+    },
+    game::{
+        board::Board,
+        colors::BoardColor,
+        mode::GameMode,
+        piece::Piece,
+        rotation::RotationKind,
+        score::Highlight,
+        snapshot::{LogEntry, Snapshot},
+        Game,
+    },
+};
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table},
     Frame,
 };
 
-use super::actions::Action;
+use super::actions::{Action, Actions};
 pub fn draw<B>(f: &mut Frame<B>, app: &mut App)
 where
     B: Backend,
 {
+    match app.state {
+        AppState::MainMenu { selected } => {
+            draw_main_menu(f, selected);
+            return;
+        }
+        AppState::Settings { selected } => {
+            draw_settings(f, app, selected);
+            return;
+        }
+        AppState::Controls {
+            selected,
+            rebinding,
+            ref message,
+        } => {
+            draw_controls(f, app.actions(), selected, rebinding, message.as_deref());
+            return;
+        }
+        _ => {}
+    }
+
     // Center the game vertically
     let tmp_rect = Layout::default()
         .direction(Direction::Vertical)
@@ -54,6 +94,7 @@ where
                 Constraint::Length(6),
                 Constraint::Length(3),
                 Constraint::Length(3),
+                Constraint::Length(3),
                 Constraint::Min(0),
                 Constraint::Length(4),
                 Constraint::Length(3),
@@ -65,8 +106,9 @@ where
     let hold_block_rect = left_panel_chunks[1];
     let level_rect = left_panel_chunks[2];
     let lines_rect = left_panel_chunks[3];
-    let score_rect = left_panel_chunks[5];
-    let high_score_rect = left_panel_chunks[6];
+    let b2b_rect = left_panel_chunks[4];
+    let score_rect = left_panel_chunks[6];
+    let high_score_rect = left_panel_chunks[7];
 
     let info_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -75,22 +117,330 @@ where
     let help_rect = info_chunks[0];
     let score_log_rect = info_chunks[1];
 
-    draw_next_blocks(f, &next_blocks_rect, &app.game);
-    draw_hold_block(f, &hold_block_rect, &app.game);
+    let mut snapshot = app.game.snapshot();
 
-    let help = draw_help();
+    // Nothing that could be used to plan ahead is shown while paused
+    let paused = matches!(app.state, AppState::Paused { .. });
+    if paused {
+        snapshot.next_pieces.clear();
+        snapshot.hold_piece = None;
+    }
+
+    draw_next_blocks(f, &next_blocks_rect, &snapshot);
+    draw_hold_block(f, &hold_block_rect, &snapshot);
+
+    let help = match &app.state {
+        AppState::Watching(playback) => draw_replay_help(playback, &app.game),
+        _ => draw_help(app.actions()),
+    };
     f.render_widget(help, help_rect);
 
-    draw_game_board(f, &game_rect, &app.game);
+    if let AppState::Paused { selected } = app.state {
+        draw_pause_menu(f, &game_rect, selected);
+    } else {
+        draw_game_board(f, &game_rect, &snapshot);
+    }
+
+    draw_score_log(f, &score_log_rect, &snapshot);
+    match snapshot.mode {
+        GameMode::Marathon => draw_level(f, &level_rect, &snapshot),
+        GameMode::Sprint { .. } | GameMode::Ultra { .. } => draw_timer(f, &level_rect, &snapshot),
+    }
+    draw_lines(f, &lines_rect, &snapshot);
+    draw_back_to_back(f, &b2b_rect, &snapshot);
+    draw_score(f, &score_rect, &snapshot);
+    draw_high_score(
+        f,
+        &high_score_rect,
+        &app.records,
+        snapshot.mode,
+        app.game.start_level(),
+    );
+
+    if let AppState::GameOver(results) = &app.state {
+        draw_game_over(f, &game_rect, results);
+    }
+}
+
+// Area of the given size in the middle of another
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+// One line per menu entry, with the selected one marked and highlighted
+fn menu_lines<'a>(entries: Vec<String>, selected: usize) -> Vec<Spans<'a>> {
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            if i == selected {
+                Spans::from(Span::styled(
+                    format!("> {entry} <"),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Spans::from(Span::styled(entry, Style::default().fg(Color::Gray)))
+            }
+        })
+        .collect()
+}
+
+fn draw_main_menu<B>(f: &mut Frame<B>, selected: usize)
+where
+    B: Backend,
+{
+    let mut text = vec![Spans::from(Span::raw(""))];
+    text.append(&mut menu_lines(
+        MenuItem::iterator().map(|item| format!("{item}")).collect(),
+        selected,
+    ));
+
+    let widget = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title("termtris"),
+        )
+        .alignment(Alignment::Center);
+
+    f.render_widget(
+        widget,
+        centered_rect(30, MenuItem::iterator().len() as u16 + 4, f.size()),
+    );
+}
+
+fn draw_settings<B>(f: &mut Frame<B>, app: &App, selected: usize)
+where
+    B: Backend,
+{
+    let mut text = vec![Spans::from(Span::raw(""))];
+    text.append(&mut menu_lines(
+        Setting::iterator()
+            .map(|setting| format!("{:<14}{:>8}", setting, app.setting_value(*setting)))
+            .collect(),
+        selected,
+    ));
+    text.push(Spans::from(Span::raw("")));
+    text.push(Spans::from(Span::styled(
+        "←/→ change   Esc back",
+        Style::default().fg(Color::Gray),
+    )));
+
+    let widget = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title("Settings"),
+        )
+        .alignment(Alignment::Center);
+
+    f.render_widget(
+        widget,
+        centered_rect(40, Setting::iterator().len() as u16 + 6, f.size()),
+    );
+}
+
+fn draw_controls<B>(
+    f: &mut Frame<B>,
+    actions: &Actions,
+    selected: usize,
+    rebinding: Option<Rebind>,
+    message: Option<&str>,
+) where
+    B: Backend,
+{
+    let hint = Style::default().fg(Color::Gray);
+    let mut text = vec![Spans::from(Span::raw(""))];
+    text.append(&mut menu_lines(
+        Action::iterator()
+            .map(|action| {
+                let keys = actions
+                    .keys(*action)
+                    .iter()
+                    .map(|key| format!("{key}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("{:<14}{:>16}", format!("{action}"), keys)
+            })
+            .collect(),
+        selected,
+    ));
+    text.push(Spans::from(Span::raw("")));
+
+    let status = match (rebinding, message) {
+        (Some(_), _) => {
+            let action = Action::iterator().nth(selected).unwrap();
+            Span::styled(
+                format!("Press a key for {action}, Esc to cancel"),
+                Style::default()
+                    .fg(Color::LightMagenta)
+                    .add_modifier(Modifier::BOLD),
+            )
+        }
+        (None, Some(message)) => {
+            Span::styled(message.to_string(), Style::default().fg(Color::Cyan))
+        }
+        (None, None) => Span::raw(""),
+    };
+    text.push(Spans::from(status));
+    text.push(Spans::from(Span::raw("")));
+    text.push(Spans::from(Span::styled("Enter rebind   a add key", hint)));
+    text.push(Spans::from(Span::styled(
+        "r reset   R reset all   Esc back",
+        hint,
+    )));
+
+    let widget = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title("Controls"),
+        )
+        .alignment(Alignment::Center);
+
+    f.render_widget(
+        widget,
+        centered_rect(50, Action::iterator().len() as u16 + 8, f.size()),
+    );
+}
+
+// Takes the place of the board while paused so the stack can't be studied
+fn draw_pause_menu<B>(f: &mut Frame<B>, rect: &Rect, selected: usize)
+where
+    B: Backend,
+{
+    let mut text = vec![Spans::from(Span::raw("")); Game::DISPLAY_HEIGHT / 2 - 2];
+    text.push(Spans::from(Span::styled(
+        "PAUSED",
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    text.push(Spans::from(Span::raw("")));
+    text.append(&mut menu_lines(
+        PauseItem::iterator()
+            .map(|item| format!("{item}"))
+            .collect(),
+        selected,
+    ));
+
+    let widget = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White)),
+        )
+        .alignment(Alignment::Center);
+
+    f.render_widget(widget, *rect);
+}
+
+// Formats a duration as m:ss.mmm
+pub fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+fn cell_to_span(content: &str, color: BoardColor) -> Span<'_> {
+    let color = match color {
+        BoardColor::Ghost => Color::Rgb(128, 128, 128),
+        BoardColor::LightBlue => Color::Rgb(60, 160, 160),
+        BoardColor::DarkBlue => Color::Rgb(60, 60, 200),
+        BoardColor::Red => Color::Rgb(160, 60, 60),
+        BoardColor::Purple => Color::Rgb(180, 60, 180),
+        BoardColor::Orange => Color::Rgb(203, 80, 60),
+        BoardColor::Green => Color::Rgb(60, 160, 60),
+        BoardColor::Yellow => Color::Rgb(200, 150, 60),
+        BoardColor::Empty => {
+            return Span::raw(content);
+        }
+    };
+
+    Span::styled(content, Style::default().fg(color).bg(color))
+}
+
+fn highlight_color(highlight: Highlight) -> Color {
+    match highlight {
+        Highlight::Normal => Color::Gray,
+        Highlight::Combo => Color::Cyan,
+        Highlight::BackToBack => Color::LightMagenta,
+        Highlight::PerfectClear => Color::LightYellow,
+    }
+}
+
+fn board_paragraph<'a>(board: &Board) -> Paragraph<'a> {
+    let text: Vec<Spans> = board
+        .visible_rows()
+        .map(|row| {
+            Spans::from(
+                row.iter()
+                    .map(|cell| match *cell {
+                        BoardColor::Empty => cell_to_span("  ", BoardColor::Empty),
+                        color => cell_to_span("██", color),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+
+    Paragraph::new(text)
+}
+
+// Draws a piece as it spawns, using the two rows of its shape that end at its lowest cells
+fn piece_spans<'a>(piece: &'static Piece, rotation: RotationKind) -> Vec<Spans<'a>> {
+    let mut text = vec![Spans::from(Span::styled(
+        "",
+        Style::default().fg(Color::Gray),
+    ))];
+
+    let shape = rotation.system().shape(piece, 0);
+    let bottom = shape
+        .iter()
+        .rposition(|row| row.iter().any(|cell| *cell != 0))
+        .unwrap_or(1)
+        .max(1);
+
+    shape[bottom - 1..=bottom].iter().for_each(|row| {
+        let mut text_row = Vec::<Span>::new();
+
+        match piece {
+            &Piece::O | &Piece::I => {}
+            _ => {
+                // Add a half block of space to center odd width blocks
+                text_row.push(Span::raw(" "));
+            }
+        }
+
+        row.iter().for_each(|cell| {
+            text_row.push(if *cell != 0 {
+                cell_to_span("██", piece.color)
+            } else {
+                Span::raw("  ")
+            });
+        });
+
+        text.push(Spans::from(text_row));
+    });
 
-    draw_score_log(f, &score_log_rect, &app.game);
-    draw_level(f, &level_rect, &app.game);
-    draw_lines(f, &lines_rect, &app.game);
-    draw_score(f, &score_rect, &app.game);
-    draw_high_score(f, &high_score_rect, &app.game);
+    text
 }
 
-fn draw_help<'a>() -> Table<'a> {
+fn draw_help<'a>(actions: &Actions) -> Table<'a> {
     let key_style = Style::default().fg(Color::Gray);
     let msg_style = Style::default()
         .fg(Color::Gray)
@@ -103,9 +453,14 @@ fn draw_help<'a>() -> Table<'a> {
     ])];
 
     for action in Action::iterator() {
-        let key = action.keys()[0];
+        let keys = actions
+            .keys(*action)
+            .iter()
+            .map(|key| format!("{key}"))
+            .collect::<Vec<_>>()
+            .join(" ");
         let row = Row::new(vec![
-            Cell::from(Span::styled(format!("{:^8}", format!("{key}")), key_style)),
+            Cell::from(Span::styled(format!("{keys:^14}"), key_style)),
             Cell::from(Span::styled(format!("{action}"), msg_style)),
         ]);
         rows.push(row);
@@ -119,16 +474,87 @@ fn draw_help<'a>() -> Table<'a> {
                 .border_style(Style::default().fg(Color::White))
                 .title("Controls"),
         )
-        .widths(&[Constraint::Length(11), Constraint::Min(20)])
+        .widths(&[Constraint::Length(14), Constraint::Min(20)])
+        .column_spacing(1)
+}
+
+// Where playback is up to, and the keys that control it
+fn draw_replay_help<'a>(playback: &Playback, game: &Game) -> Table<'a> {
+    let key_style = Style::default().fg(Color::Gray);
+    let msg_style = Style::default()
+        .fg(Color::Gray)
+        .add_modifier(Modifier::BOLD);
+
+    let replay = playback.replay();
+    let status = match (playback.is_finished(), playback.is_paused()) {
+        (true, _) => match replay.summary.outcome {
+            Some(outcome) => format!("{outcome}"),
+            None => "Ended".to_string(),
+        },
+        (false, true) => "Paused".to_string(),
+        (false, false) => "Playing".to_string(),
+    };
+    let seek = match playback.seek.as_str() {
+        "" => "-".to_string(),
+        seek => format!("{seek}_"),
+    };
+    let info = [
+        ("Mode", format!("{}", replay.config.mode)),
+        (
+            "Piece",
+            format!("{}/{}", game.pieces(), replay.summary.pieces),
+        ),
+        ("Speed", format!("{}x", playback.speed())),
+        ("Status", status),
+        ("Seek", seek),
+    ];
+    let keys = [
+        ("Space", "Pause"),
+        (".", "Step frame"),
+        ("Left Right", "Previous/next piece"),
+        ("Up Down", "Speed"),
+        ("0-9 Enter", "Seek to piece"),
+        ("Esc", "Quit"),
+    ];
+
+    // Empty rows for padding
+    let mut rows = vec![Row::new(vec![Cell::from(""), Cell::from("")])];
+    for (name, value) in info {
+        rows.push(Row::new(vec![
+            Cell::from(Span::styled(format!("{name:^14}"), key_style)),
+            Cell::from(Span::styled(value, msg_style)),
+        ]));
+    }
+    rows.push(Row::new(vec![Cell::from(""), Cell::from("")]));
+    for (key, action) in keys {
+        rows.push(Row::new(vec![
+            Cell::from(Span::styled(format!("{key:^14}"), key_style)),
+            Cell::from(Span::styled(action, msg_style)),
+        ]));
+    }
+
+    Table::new(rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::White))
+                .title("Replay"),
+        )
+        .widths(&[Constraint::Length(14), Constraint::Min(20)])
         .column_spacing(1)
 }
 
-fn draw_next_blocks<B>(f: &mut Frame<B>, rect: &Rect, game: &Game)
+fn draw_next_blocks<B>(f: &mut Frame<B>, rect: &Rect, snapshot: &Snapshot)
 where
     B: Backend,
 {
-    let widget = game
-        .next_pieces_paragraph()
+    let spans: Vec<Spans> = snapshot
+        .next_pieces
+        .iter()
+        .flat_map(|piece| piece_spans(piece, snapshot.rotation))
+        .collect();
+    let widget = Paragraph::new(spans)
         .block(
             Block::default()
                 .borders(Borders::ALL)
@@ -141,12 +567,15 @@ where
     f.render_widget(widget, *rect);
 }
 
-fn draw_hold_block<B>(f: &mut Frame<B>, rect: &Rect, game: &Game)
+fn draw_hold_block<B>(f: &mut Frame<B>, rect: &Rect, snapshot: &Snapshot)
 where
     B: Backend,
 {
-    let widget = game
-        .hold_piece_paragraph()
+    let spans = match snapshot.hold_piece {
+        Some(piece) => piece_spans(piece, snapshot.rotation),
+        None => vec![Spans::from(Span::raw(""))],
+    };
+    let widget = Paragraph::new(spans)
         .block(
             Block::default()
                 .borders(Borders::ALL)
@@ -159,12 +588,11 @@ where
     f.render_widget(widget, *rect);
 }
 
-fn draw_game_board<B>(f: &mut Frame<B>, rect: &Rect, game: &Game)
+fn draw_game_board<B>(f: &mut Frame<B>, rect: &Rect, snapshot: &Snapshot)
 where
     B: Backend,
 {
-    let board: Paragraph = game.get_board_paragraph();
-    let widget = board
+    let widget = board_paragraph(&snapshot.board)
         .block(
             Block::default()
                 .borders(Borders::ALL)
@@ -176,91 +604,250 @@ where
     f.render_widget(widget, *rect);
 }
 
-fn draw_level<B>(f: &mut Frame<B>, rect: &Rect, game: &Game)
+fn draw_level<B>(f: &mut Frame<B>, rect: &Rect, snapshot: &Snapshot)
 where
     B: Backend,
 {
-    let widget = game
-        .level_paragraph()
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .style(Style::default().fg(Color::White))
-                .title("Level"),
-        )
-        .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::White));
+    let style = if snapshot.level < 15 {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+            .add_modifier(Modifier::BOLD)
+            .fg(Color::LightRed)
+    };
+    let widget = Paragraph::new(Spans::from(Span::styled(
+        format!("{}", snapshot.level),
+        style,
+    )))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("Level"),
+    )
+    .alignment(Alignment::Center)
+    .style(Style::default().fg(Color::White));
 
     f.render_widget(widget, *rect);
 }
 
-fn draw_lines<B>(f: &mut Frame<B>, rect: &Rect, game: &Game)
+fn draw_timer<B>(f: &mut Frame<B>, rect: &Rect, snapshot: &Snapshot)
 where
     B: Backend,
 {
-    let widget = game
-        .lines_paragraph()
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .style(Style::default().fg(Color::White))
-                .title("Lines"),
-        )
-        .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::White));
+    // Ultra counts down to the end, sprint counts up from the start
+    let time = match snapshot.mode {
+        GameMode::Ultra { duration } => duration.saturating_sub(snapshot.elapsed),
+        _ => snapshot.elapsed,
+    };
+    let widget = Paragraph::new(Spans::from(Span::styled(
+        format_time(time),
+        Style::default().add_modifier(Modifier::BOLD),
+    )))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("Time"),
+    )
+    .alignment(Alignment::Center)
+    .style(Style::default().fg(Color::White));
 
     f.render_widget(widget, *rect);
 }
 
-fn draw_score<B>(f: &mut Frame<B>, rect: &Rect, game: &Game)
+fn draw_lines<B>(f: &mut Frame<B>, rect: &Rect, snapshot: &Snapshot)
 where
     B: Backend,
 {
-    let widget = game
-        .score_paragraph()
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .style(Style::default().fg(Color::White))
-                .title("Score"),
-        )
-        .alignment(Alignment::Left)
-        .style(Style::default().fg(Color::White));
+    let text = if let GameMode::Sprint { lines } = snapshot.mode {
+        format!("{}/{}", snapshot.cleared_lines, lines)
+    } else if let GameMode::Ultra { .. } = snapshot.mode {
+        format!("{}", snapshot.cleared_lines)
+    } else if snapshot.level >= 15 {
+        format!("{}", snapshot.lines)
+    } else {
+        format!("{}->{}", snapshot.lines, snapshot.lines_goal)
+    };
+    let widget = Paragraph::new(Spans::from(Span::styled(
+        text,
+        Style::default().add_modifier(Modifier::BOLD),
+    )))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("Lines"),
+    )
+    .alignment(Alignment::Center)
+    .style(Style::default().fg(Color::White));
 
     f.render_widget(widget, *rect);
 }
 
-fn draw_high_score<B>(f: &mut Frame<B>, rect: &Rect, game: &Game)
+// How many back to back clears in a row the current chain has scored
+fn draw_back_to_back<B>(f: &mut Frame<B>, rect: &Rect, snapshot: &Snapshot)
 where
     B: Backend,
 {
-    let widget = game
-        .high_score_paragraph()
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .style(Style::default().fg(Color::White))
-                .title("High─Score"),
-        )
-        .alignment(Alignment::Left)
-        .style(Style::default().fg(Color::White));
+    let text = match snapshot.back_to_back {
+        Some(streak) if streak > 0 => format!(" x{streak}"),
+        _ => " -".to_string(),
+    };
+    let widget = Paragraph::new(Spans::from(Span::styled(
+        text,
+        Style::default().fg(highlight_color(Highlight::BackToBack)),
+    )))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("B2B"),
+    )
+    .alignment(Alignment::Left);
+
+    f.render_widget(widget, *rect);
+}
+
+fn draw_score<B>(f: &mut Frame<B>, rect: &Rect, snapshot: &Snapshot)
+where
+    B: Backend,
+{
+    let widget = Paragraph::new(vec![
+        Spans::from(Span::styled(
+            format!(" {}", snapshot.score),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(Span::styled(
+            format!("+{}", snapshot.last_turn_score),
+            Style::default().add_modifier(Modifier::ITALIC),
+        )),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("Score"),
+    )
+    .alignment(Alignment::Left)
+    .style(Style::default().fg(Color::White));
+
+    f.render_widget(widget, *rect);
+}
+
+fn draw_high_score<B>(f: &mut Frame<B>, rect: &Rect, records: &Records, mode: GameMode, level: u8)
+where
+    B: Backend,
+{
+    let (title, text) = match mode {
+        GameMode::Marathon => ("High─Score", format!(" {}", records.high_score(level))),
+        GameMode::Sprint { lines } => (
+            "Best",
+            match records.sprint_best(lines) {
+                Some(time) => format!(" {}", format_time(time)),
+                None => " -".to_string(),
+            },
+        ),
+        GameMode::Ultra { duration } => ("Best", format!(" {}", records.ultra_best(duration))),
+    };
+    let widget = Paragraph::new(Spans::from(Span::styled(
+        text,
+        Style::default().add_modifier(Modifier::BOLD),
+    )))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title(title),
+    )
+    .alignment(Alignment::Left)
+    .style(Style::default().fg(Color::White));
 
     f.render_widget(widget, *rect);
 }
 
-fn draw_score_log<B>(f: &mut Frame<B>, rect: &Rect, game: &Game)
+fn draw_score_log<B>(f: &mut Frame<B>, rect: &Rect, snapshot: &Snapshot)
 where
     B: Backend,
 {
-    let widget = game
-        .score_log_paragraph()
+    let widget = Paragraph::new(
+        snapshot
+            .score_log
+            .iter()
+            .map(|LogEntry { text, highlight }| {
+                Spans::from(Span::styled(
+                    text.clone(),
+                    Style::default().fg(highlight_color(*highlight)),
+                ))
+            })
+            .collect::<Vec<_>>(),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title(format!("Log─(seed {})", snapshot.seed)),
+    )
+    .alignment(Alignment::Left);
+
+    f.render_widget(widget, *rect);
+}
+
+// Popup over the board with the final stats of a finished game
+fn draw_game_over<B>(f: &mut Frame<B>, rect: &Rect, results: &Results)
+where
+    B: Backend,
+{
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let hint = Style::default().fg(Color::Gray);
+    let pps = results.pieces as f64 / results.time.as_secs_f64().max(0.001);
+
+    let mut text = vec![
+        Spans::from(Span::raw("")),
+        Spans::from(Span::styled(format!("{}", results.mode), bold)),
+        Spans::from(Span::raw("")),
+        Spans::from(Span::raw(format!("{:<7}{:>10}", "Score", results.score))),
+        Spans::from(Span::raw(format!("{:<7}{:>10}", "Lines", results.lines))),
+        Spans::from(Span::raw(format!("{:<7}{:>10}", "Level", results.level))),
+        Spans::from(Span::raw(format!("{:<7}{:>10}", "Pieces", results.pieces))),
+        Spans::from(Span::raw(format!(
+            "{:<7}{:>10}",
+            "PCs", results.perfect_clears
+        ))),
+        Spans::from(Span::raw(format!("{:<7}{:>10.2}", "PPS", pps))),
+        Spans::from(Span::raw(format!(
+            "{:<7}{:>10}",
+            "Time",
+            format_time(results.time)
+        ))),
+        Spans::from(Span::raw("")),
+    ];
+    if results.new_best {
+        text.push(Spans::from(Span::styled(
+            "New best!",
+            bold.fg(Color::LightMagenta),
+        )));
+        text.push(Spans::from(Span::raw("")));
+    }
+    text.push(Spans::from(Span::styled("Enter  Play again", hint)));
+    text.push(Spans::from(Span::styled("Esc    Main menu ", hint)));
+    text.push(Spans::from(Span::styled("q      Quit      ", hint)));
+
+    let popup = Rect {
+        x: rect.x,
+        y: rect.y + rect.height / 6,
+        width: rect.width,
+        height: (text.len() as u16 + 2).min(rect.height),
+    };
+    let widget = Paragraph::new(text)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .style(Style::default().fg(Color::White))
-                .title("Log"),
+                .title(format!("{}", results.outcome)),
         )
-        .alignment(Alignment::Left);
+        .alignment(Alignment::Center);
 
-    f.render_widget(widget, *rect);
+    f.render_widget(Clear, popup);
+    f.render_widget(widget, popup);
 }
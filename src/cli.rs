@@ -0,0 +1,152 @@
+use clap::{Args, Parser, Subcommand};
+use std::{fmt::Display, path::PathBuf, slice::Iter};
+
+use termtris::game::{
+    config::GameConfig, level::Level, lock::LockReset, mode::GameMode, randomizer::RandomizerKind,
+    rotation::RotationKind, score::SpinRule,
+};
+
+// Command line options, anything not given here comes from the config file or the defaults
+#[derive(Parser)]
+#[command(version, about = "Tetris in the terminal")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[arg(
+        long,
+        global = true,
+        value_parser = clap::value_parser!(u8).range(1..=Level::MAX as i64),
+        help = "Level a marathon starts at, from 1 to 15"
+    )]
+    pub level: Option<u8>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Seed for the piece randomizer, every game gets the same pieces"
+    )]
+    pub seed: Option<u64>,
+
+    #[command(flatten)]
+    pub rules: Rules,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Config file to use instead of the default one"
+    )]
+    pub config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        help = "Directory replays and records are saved to"
+    )]
+    pub data_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "MS",
+        default_value_t = 16,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "How often the screen is redrawn, in milliseconds"
+    )]
+    pub tick_rate: u64,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Start a marathon straight away")]
+    Marathon,
+    #[command(about = "Start a sprint straight away")]
+    Sprint {
+        #[arg(
+            default_value_t = GameMode::SPRINT_LINES,
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Lines to clear"
+        )]
+        lines: u32,
+    },
+    #[command(about = "Start an ultra straight away")]
+    Ultra {
+        #[arg(
+            default_value_t = GameMode::ULTRA_DURATION.as_secs(),
+            value_parser = clap::value_parser!(u64).range(1..),
+            help = "Length in seconds"
+        )]
+        seconds: u64,
+    },
+    #[command(about = "Watch a recorded game")]
+    Replay { file: PathBuf },
+    #[command(about = "Play a recorded game back without drawing it and check its results")]
+    Verify { file: PathBuf },
+}
+
+// The rules games are played with, the same choices as on the settings screen
+#[derive(Args)]
+pub struct Rules {
+    #[arg(
+        long,
+        global = true,
+        value_parser = |name: &str| parse_kind(RandomizerKind::iterator(), name),
+        help = "7-bag, 14-bag, random, nes or tgm"
+    )]
+    pub randomizer: Option<RandomizerKind>,
+
+    #[arg(
+        long,
+        global = true,
+        value_parser = |name: &str| parse_kind(RotationKind::iterator(), name),
+        help = "srs, ars, nes or sega"
+    )]
+    pub rotation: Option<RotationKind>,
+
+    #[arg(
+        long,
+        global = true,
+        value_parser = |name: &str| parse_kind(LockReset::iterator(), name),
+        help = "infinite, move or step"
+    )]
+    pub lock_reset: Option<LockReset>,
+
+    #[arg(
+        long,
+        global = true,
+        value_parser = |name: &str| parse_kind(SpinRule::iterator(), name),
+        help = "t-only, all-mini or all-spin"
+    )]
+    pub spins: Option<SpinRule>,
+}
+
+impl Cli {
+    // Options for every game started this session, with whatever was given on the command line filled in
+    pub fn game_config(&self) -> GameConfig {
+        let defaults = GameConfig::default();
+        GameConfig {
+            seed: self.seed,
+            level: self.level.unwrap_or(defaults.level),
+            randomizer: self.rules.randomizer.unwrap_or(defaults.randomizer),
+            rotation: self.rules.rotation.unwrap_or(defaults.rotation),
+            lock_reset: self.rules.lock_reset.unwrap_or(defaults.lock_reset),
+            spin_rule: self.rules.spins.unwrap_or(defaults.spin_rule),
+            ..defaults
+        }
+    }
+}
+
+// Find a kind by the name it has on the settings screen, in lower case with dashes for spaces
+fn parse_kind<T: Display + Copy>(kinds: Iter<'static, T>, name: &str) -> Result<T, String> {
+    let cli_name = |kind: &T| kind.to_string().to_lowercase().replace(' ', "-");
+    let mut names = Vec::new();
+    for kind in kinds {
+        if cli_name(kind) == name.to_lowercase() {
+            return Ok(*kind);
+        }
+        names.push(cli_name(kind));
+    }
+    Err(format!("expected one of {}", names.join(", ")))
+}
@@ -1,43 +1,12 @@
-use super::{
-    colors::{self, BoardColor},
-    Game,
-};
+use super::{colors::BoardColor, Game};
 
-use tui::{
-    text::{Span, Spans},
-    widgets::Paragraph,
-};
-
-// Struct to store the state of the game board with an impl to turn it into a Paragrpah for rendering
+// Struct to store the state of the game board, row 0 is the bottom of the board
 #[derive(Clone)]
 pub struct Board(pub [[BoardColor; Game::WIDTH]; Game::HEIGHT]);
 
 impl Board {
-    pub fn reset(&mut self) {
-        self.0 = [[BoardColor::Empty; Game::WIDTH]; Game::HEIGHT];
-    }
-}
-
-impl<'a> From<Board> for Paragraph<'a> {
-    fn from(board: Board) -> Paragraph<'a> {
-        let mut text = Vec::new();
-
-        board.0.iter().enumerate().for_each(|(i, row)| {
-            if i < Game::HEIGHT - Game::DISPLAY_HEIGHT {
-                let mut text_row = Vec::<Span>::new();
-
-                row.iter().for_each(|cell| {
-                    text_row.push(match *cell {
-                        BoardColor::Empty => colors::cell_to_span("  ", BoardColor::Empty),
-                        color => colors::cell_to_span("██", color),
-                    });
-                });
-
-                text.push(Spans::from(text_row));
-            }
-        });
-
-        text.reverse();
-        Paragraph::new(text)
+    // Iterator over the rows that are actually shown on screen, from the top down
+    pub fn visible_rows(&self) -> impl Iterator<Item = &[BoardColor; Game::WIDTH]> {
+        self.0[..Game::DISPLAY_HEIGHT].iter().rev()
     }
 }
@@ -0,0 +1,58 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+// Source of time for everything timing related in the game, measured from an arbitrary starting point
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+// Clock backed by the real monotonic system time
+pub struct SystemClock {
+    start: Instant,
+}
+
+// I can't think of a reason to have a Default impl for any structs in this project but I'm open to any reasons
+#[allow(clippy::new_without_default)]
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// Clock that only moves when told to, for tests and replays
+// Clones share the same time, so one can be given to a Game and another kept around to advance it
+#[derive(Clone)]
+pub struct ManualClock(Rc<Cell<Duration>>);
+
+// I can't think of a reason to have a Default impl for any structs in this project but I'm open to any reasons
+#[allow(clippy::new_without_default)]
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock(Rc::new(Cell::new(Duration::ZERO)))
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.0.set(self.0.get() + by);
+    }
+
+    pub fn set(&self, to: Duration) {
+        self.0.set(to);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.0.get()
+    }
+}
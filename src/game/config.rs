@@ -0,0 +1,34 @@
+use super::{
+    handling::Handling, lock::LockReset, mode::GameMode, randomizer::RandomizerKind,
+    rotation::RotationKind, score::SpinRule,
+};
+
+// Options that decide how a game plays out, everything not set here is fixed by the engine
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    // Seed for the piece randomizer, a random one is picked when this is None
+    pub seed: Option<u64>,
+    pub mode: GameMode,
+    // Level a marathon starts at, other modes always start at 1
+    pub level: u8,
+    pub randomizer: RandomizerKind,
+    pub rotation: RotationKind,
+    pub handling: Handling,
+    pub lock_reset: LockReset,
+    pub spin_rule: SpinRule,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            seed: None,
+            mode: GameMode::default(),
+            level: 1,
+            randomizer: RandomizerKind::default(),
+            rotation: RotationKind::default(),
+            handling: Handling::default(),
+            lock_reset: LockReset::default(),
+            spin_rule: SpinRule::default(),
+        }
+    }
+}
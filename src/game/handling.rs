@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+// How held inputs repeat, timed by the game itself instead of the terminal's key repeat
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Handling {
+    // Delayed auto shift, how long a direction has to be held before the piece starts sliding
+    pub das: Duration,
+    // Auto repeat rate, the time between moves once DAS has charged, zero slides straight to the wall
+    pub arr: Duration,
+    // Soft drop factor, how many times faster than gravity a soft drop falls, zero drops straight down
+    pub sdf: u32,
+}
+
+impl Handling {
+    pub const DAS: Duration = Duration::from_millis(167);
+    pub const ARR: Duration = Duration::from_millis(33);
+    pub const SDF: u32 = 20;
+    // Anything faster is as good as instant, and an ARR under a millisecond is as good as zero
+    pub const MAX_SDF: u32 = 100;
+    pub const MIN_ARR: Duration = Duration::from_millis(1);
+
+    pub fn is_valid(&self) -> bool {
+        self.sdf <= Self::MAX_SDF && (self.arr.is_zero() || self.arr >= Self::MIN_ARR)
+    }
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            das: Self::DAS,
+            arr: Self::ARR,
+            sdf: Self::SDF,
+        }
+    }
+}
+
+// Inputs the game keeps track of while they're held down
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HeldInput {
+    Left,
+    Right,
+    SoftDrop,
+}
+
+// A direction being held, and how far into its auto shift it is
+#[derive(Debug, Copy, Clone)]
+pub(super) struct Autoshift {
+    pub direction: isize,
+    pub since: Duration,
+    pub repeats: u32,
+}
+
+impl Autoshift {
+    pub fn new(direction: isize, since: Duration) -> Self {
+        Autoshift {
+            direction,
+            since,
+            repeats: 0,
+        }
+    }
+}
@@ -5,10 +5,10 @@ pub struct Level {
     pub level: u8,
     pub lines: u32,
     pub lines_goal: u32,
+    // Whether clearing lines moves up a level, if not the gravity stays the same all game
+    pub progression: bool,
 }
 
-// I can't think of a reason to have a Default impl for any structs in this project but I'm open to any reasons
-#[allow(clippy::new_without_default)]
 impl Level {
     // Speed information from here: https://tetris.fandom.com/wiki/Tetris_Worlds (rounded slightly)
     // Allowing zero prefixed literals for better alignment
@@ -32,14 +32,27 @@ impl Level {
         Duration::from_millis(0007),
     ];
 
-    pub fn new() -> Self {
-        let level = 1;
+    pub const MAX: u8 = 15;
+
+    // A level that goes up as lines are cleared, starting from the given one
+    pub fn new(level: u8) -> Self {
         let lines = 0;
-        let lines_goal = level as u32 * 5;
+        let lines_goal = Self::goal(level);
         Level {
             level,
             lines,
             lines_goal,
+            progression: true,
+        }
+    }
+
+    // A level that never changes, so the gravity stays fixed
+    pub fn fixed(level: u8) -> Self {
+        Level {
+            level,
+            lines: 0,
+            lines_goal: 0,
+            progression: false,
         }
     }
 
@@ -47,11 +60,17 @@ impl Level {
         Self::SPEEDS[self.level as usize]
     }
 
+    // Variable goal, each level takes 5 lines times the level number to clear, however high the game started
+    // Goals are counted from the start of the game, so a level 10 start needs 50 lines for level 11 and 105 for 12
+    fn goal(level: u8) -> u32 {
+        level as u32 * 5
+    }
+
     pub fn add_lines(&mut self, lines: u32) {
         self.lines += lines;
-        if self.lines >= self.lines_goal && self.level < 15 {
+        while self.progression && self.lines >= self.lines_goal && self.level < Self::MAX {
             self.level += 1;
-            self.lines_goal += self.level as u32 * 5;
+            self.lines_goal += Self::goal(self.level);
         }
     }
 }
@@ -0,0 +1,34 @@
+use std::fmt::{self, Display};
+
+// What restarts the lock delay once a piece is resting on something
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum LockReset {
+    // Every move or rotation restarts it, so a piece can be kept spinning forever
+    Infinite,
+    // Moves and rotations restart it up to a limit, which is refilled when the piece reaches a new lowest row
+    #[default]
+    Move,
+    // Only falling to a lower row restarts it
+    Step,
+}
+
+impl LockReset {
+    // How many times a move or rotation can restart the lock delay with move reset
+    pub const MOVE_LIMIT: u32 = 15;
+
+    // Iterator over all reset policies
+    pub fn iterator() -> std::slice::Iter<'static, LockReset> {
+        static POLICIES: [LockReset; 3] = [LockReset::Infinite, LockReset::Move, LockReset::Step];
+        POLICIES.iter()
+    }
+}
+
+impl Display for LockReset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockReset::Infinite => write!(f, "Infinite"),
+            LockReset::Move => write!(f, "Move"),
+            LockReset::Step => write!(f, "Step"),
+        }
+    }
+}
@@ -1,86 +1,237 @@
-mod board;
-mod colors;
-mod level;
-mod piece;
-mod score;
-
-use std::{collections::VecDeque, time::Instant};
-use tui::{
-    style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::Paragraph,
-};
+pub mod board;
+pub mod clock;
+pub mod colors;
+pub mod config;
+pub mod handling;
+pub mod level;
+pub mod lock;
+pub mod mode;
+pub mod piece;
+pub mod randomizer;
+pub mod replay;
+pub mod rotation;
+pub mod score;
+pub mod snapshot;
+
+use rand::Rng;
+use std::{collections::VecDeque, time::Duration};
 
 use self::{
     board::Board,
+    clock::Clock,
     colors::BoardColor,
-    piece::Piece,
-    score::{Lines, Score, ScoreEvent, TSpins},
+    config::GameConfig,
+    handling::{Autoshift, Handling, HeldInput},
+    level::Level,
+    lock::LockReset,
+    mode::{GameMode, Outcome},
+    piece::{Piece, PieceKind},
+    randomizer::Randomizer,
+    replay::{Input, Replay, Summary},
+    rotation::{RotationKind, RotationSystem},
+    score::{Highlight, Lines, Score, ScoreEvent, SpinRule, TSpins},
+    snapshot::{LogEntry, Snapshot},
 };
 
 // Struct for storing the overall game state with methods to interact with it
-pub struct Game<'a> {
+// Nothing in here knows about the terminal, the renderer works from a Snapshot
+pub struct Game {
     board: Board,
     pub score: Score,
-    piece_bag: VecDeque<&'a Piece>,
-    cur_piece: &'a Piece,
-    next_piece: &'a Piece,
-    hold_piece: Option<&'a Piece>,
+    mode: GameMode,
+    outcome: Option<Outcome>,
+    seed: u64,
+    randomizer: Box<dyn Randomizer>,
+    rotation_kind: RotationKind,
+    rotation: &'static dyn RotationSystem,
+    piece_queue: VecDeque<&'static Piece>,
+    cur_piece: &'static Piece,
+    next_piece: &'static Piece,
+    hold_piece: Option<&'static Piece>,
     can_hold: bool,
     cur_rotation: u8,
+    pieces: u32,
     piece_offset: (isize, isize),
     ghost_offset: (isize, isize),
-    score_log: VecDeque<Spans<'a>>,
-    last_move: Instant,
+    score_log: VecDeque<LogEntry>,
+    clock: Box<dyn Clock>,
+    // The clock's time as of the latest call into the game, read once per call so a replay of it plays out the same
+    clock_time: Duration,
+    paused_at: Option<Duration>,
+    paused_total: Duration,
+    start_time: Duration,
+    end_time: Option<Duration>,
+    last_gravity: Duration,
+    lock_reset: LockReset,
+    // When the piece came to rest or last had its lock delay restarted, None until it lands on a new lowest row
+    lock_since: Option<Duration>,
+    lock_resets: u32,
+    lowest_row: isize,
+    // The kick used if the last thing the piece did was rotate, for spin checks
+    last_kick: Option<Kick>,
+    spin_rule: SpinRule,
+    handling: Handling,
+    held_left: bool,
+    held_right: bool,
+    soft_dropping: bool,
+    autoshift: Option<Autoshift>,
+    // Options the game was started with and every input since, None once the replay has been taken
+    config: GameConfig,
+    recording: Option<Vec<(Duration, Input)>>,
+}
+
+// Which of the rotation system's tests a rotation passed
+#[derive(Copy, Clone)]
+struct Kick {
+    // 0 for turning in place, then each kick offset in order
+    test: usize,
+    half_turn: bool,
 }
 
-// I can't think of a reason to have a Default impl for any structs in this project but I'm open to any reasons
-#[allow(clippy::new_without_default)]
-impl<'a> Game<'a> {
+impl Kick {
+    // The fifth test of an SRS quarter turn is the kick TST and fin setups rely on, 180 kicks never count
+    fn is_tst_or_fin(&self) -> bool {
+        !self.half_turn && self.test == 4
+    }
+}
+
+impl Game {
     pub const WIDTH: usize = 10;
     pub const HEIGHT: usize = 40;
     pub const DISPLAY_HEIGHT: usize = 20;
 
-    pub fn new() -> Self {
+    pub const LOCK_DELAY: Duration = Duration::from_millis(500);
+    // Number of pieces shown after the next piece
+    const PREVIEW_LEN: usize = 5;
+
+    // Create a game with the given options that takes all of its timing from the given clock
+    pub fn with_config(config: GameConfig, clock: Box<dyn Clock>) -> Self {
         let board = Board([[BoardColor::Empty; Game::WIDTH]; Game::HEIGHT]);
-        let score = Score::new();
-        let mut piece_bag = VecDeque::from(Piece::random_bag());
-        let cur_piece = piece_bag.pop_front().unwrap();
-        let next_piece = piece_bag.pop_front().unwrap();
+        let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        // Kept as the game is actually played, so the replay and records agree with it
+        let config = GameConfig {
+            seed: Some(seed),
+            level: config.level.clamp(1, Level::MAX),
+            ..config
+        };
+        let level = match config.mode {
+            GameMode::Marathon => Level::new(config.level),
+            GameMode::Sprint { .. } => Level::new(1),
+            GameMode::Ultra { .. } => Level::fixed(1),
+        };
+        let score = Score::new(level);
+        let mut randomizer = config.randomizer.build(seed);
+        let cur_piece = randomizer.next_piece();
+        let next_piece = randomizer.next_piece();
+        let piece_queue = (0..Self::PREVIEW_LEN)
+            .map(|_| randomizer.next_piece())
+            .collect();
         let hold_piece = None;
         let can_hold = true;
         let cur_rotation = 0_u8;
+        let pieces = 0;
         let piece_offset = (0, 0);
         let ghost_offset = (0, 0);
-        let score_log = VecDeque::from(vec![Spans::from(Span::raw("-")); 7]);
-        let last_move = Instant::now();
+        let score_log = VecDeque::from(vec![
+            LogEntry {
+                text: "-".to_string(),
+                highlight: Highlight::Normal,
+            };
+            7
+        ]);
+        let start_time = clock.now();
+        let last_gravity = start_time;
 
         let mut game = Game {
             board,
             score,
+            mode: config.mode,
+            outcome: None,
+            seed,
+            randomizer,
+            rotation_kind: config.rotation,
+            rotation: config.rotation.system(),
             cur_piece,
             next_piece,
             hold_piece,
             can_hold,
             cur_rotation,
+            pieces,
             piece_offset,
             ghost_offset,
-            piece_bag,
+            piece_queue,
             score_log,
-            last_move,
+            clock,
+            clock_time: start_time,
+            paused_at: None,
+            paused_total: Duration::ZERO,
+            start_time,
+            end_time: None,
+            last_gravity,
+            lock_reset: config.lock_reset,
+            lock_since: None,
+            lock_resets: 0,
+            lowest_row: 0,
+            last_kick: None,
+            spin_rule: config.spin_rule,
+            handling: config.handling,
+            held_left: false,
+            held_right: false,
+            soft_dropping: false,
+            autoshift: None,
+            config,
+            recording: Some(Vec::new()),
         };
 
         game.reset_piece(false);
         game
     }
 
-    pub fn get_board_paragraph(&self) -> Paragraph<'a> {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: self.board_with_piece(),
+            next_pieces: std::iter::once(self.next_piece)
+                .chain(self.piece_queue.iter().copied())
+                .collect(),
+            hold_piece: self.hold_piece,
+            score: self.score.score(),
+            last_turn_score: self.score.last_turn_score(),
+            level: self.score.level(),
+            lines: self.score.lines(),
+            lines_goal: self.score.lines_goal(),
+            cleared_lines: self.score.cleared_lines(),
+            back_to_back: self.score.back_to_back(),
+            mode: self.mode,
+            elapsed: self.elapsed(),
+            score_log: Vec::from(self.score_log.clone()),
+            seed: self.seed,
+            rotation: self.rotation_kind,
+        }
+    }
+
+    // Seed the piece sequence was generated from, the same seed always gives the same pieces
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // The locked cells, without the current piece or its ghost
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn current_piece(&self) -> &'static Piece {
+        self.cur_piece
+    }
+
+    // Row and column of the top left corner of the current piece's shape, and its rotation
+    pub fn piece_position(&self) -> ((isize, isize), u8) {
+        (self.piece_offset, self.cur_rotation)
+    }
+
+    fn board_with_piece(&self) -> Board {
         let mut board_copy = self.board.clone();
 
-        for (i, row) in self.cur_piece.shapes[self.cur_rotation as usize]
-            .iter()
-            .enumerate()
-        {
+        for (i, row) in self.current_shape().iter().enumerate() {
             let row_idx = self.piece_offset.0 - i as isize;
             let ghost_row_idx = self.ghost_offset.0 - i as isize;
             for (j, cell) in row.iter().enumerate() {
@@ -99,157 +250,397 @@ impl<'a> Game<'a> {
             }
         }
 
-        board_copy.into()
+        board_copy
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    // Level the game started at, only ever above 1 for a marathon
+    pub fn start_level(&self) -> u8 {
+        match self.mode {
+            GameMode::Marathon => self.config.level,
+            _ => 1,
+        }
+    }
+
+    // Number of pieces locked into the board so far
+    pub fn pieces(&self) -> u32 {
+        self.pieces
     }
 
-    pub fn score_log_paragraph(&self) -> Paragraph<'a> {
-        Paragraph::new(Vec::from(self.score_log.clone()))
+    // Why the game ended, or None if it's still going
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
     }
 
-    pub fn score_paragraph(&self) -> Paragraph<'a> {
-        Paragraph::new(vec![
-            Spans::from(Span::styled(
-                format!(" {}", self.score.score()),
-                Style::default().add_modifier(Modifier::BOLD),
-            )),
-            Spans::from(Span::styled(
-                format!("+{}", self.score.last_turn_score()),
-                Style::default().add_modifier(Modifier::ITALIC),
-            )),
-        ])
+    // Time since the game started according to its clock, stops counting once the game is over
+    pub fn elapsed(&self) -> Duration {
+        self.end_time.unwrap_or_else(|| self.now()) - self.start_time
     }
 
-    pub fn high_score_paragraph(&self) -> Paragraph<'a> {
-        Paragraph::new(Spans::from(Span::styled(
-            format!(" {}", self.score.high_score()),
-            Style::default().add_modifier(Modifier::BOLD),
-        )))
+    // The game so far as a replay, recording stops once this has been called
+    // Returns None if it was already taken or nothing was ever locked
+    pub fn take_replay(&mut self) -> Option<Replay> {
+        if self.pieces == 0 && self.outcome.is_none() {
+            return None;
+        }
+        // A game left part way through is paused so the replay ends at the time it was left
+        if self.outcome.is_none() {
+            self.pause();
+        }
+        Some(Replay {
+            config: self.config.clone(),
+            summary: self.summary(),
+            inputs: self.recording.take()?,
+        })
     }
 
-    pub fn next_pieces_paragraph(&self) -> Paragraph<'a> {
-        let mut spans = Vec::<Spans>::new();
-        spans.append(&mut self.next_piece.into());
+    // How the game stands, as kept in a replay
+    pub fn summary(&self) -> Summary {
+        Summary {
+            outcome: self.outcome,
+            score: self.score.score(),
+            lines: self.score.cleared_lines(),
+            pieces: self.pieces,
+            time: self.elapsed(),
+        }
+    }
+
+    // Make the call a recorded input stands for, used to play replays back
+    pub fn apply(&mut self, input: Input) {
+        match input {
+            Input::Tick => self.update(),
+            Input::Press(input) => self.press(input),
+            Input::Release(input) => self.release(input),
+            Input::RotateLeft => self.rotate_left(),
+            Input::RotateRight => self.rotate_right(),
+            Input::Rotate180 => self.rotate_180(),
+            Input::HardDrop => self.hard_drop(),
+            Input::Hold => self.hold(),
+            Input::Pause => self.pause(),
+            Input::Resume => self.resume(),
+        }
+    }
 
-        for piece in self.piece_bag.iter().take(5) {
-            spans.append(&mut piece.to_owned().into());
+    // Every call from outside starts here, reading the clock once for the whole call
+    fn record(&mut self, input: Input) {
+        self.clock_time = self.clock.now();
+        let at = self.clock_time - self.start_time;
+        if let Some(recording) = &mut self.recording {
+            recording.push((at, input));
         }
+    }
 
-        Paragraph::new(spans)
+    // Stop the game's time, nothing happens until it's resumed
+    pub fn pause(&mut self) {
+        self.record(Input::Pause);
+        if self.paused_at.is_none() {
+            self.paused_at = Some(self.clock_time);
+        }
     }
 
-    pub fn hold_piece_paragraph(&self) -> Paragraph<'a> {
-        if let Some(piece) = self.hold_piece {
-            let spans: Vec<Spans> = piece.into();
-            Paragraph::new(spans)
-        } else {
-            Paragraph::new(Spans::from(Span::raw("")))
+    pub fn resume(&mut self) {
+        self.record(Input::Resume);
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_total += self.clock_time - paused_at;
         }
     }
 
-    pub fn level_paragraph(&self) -> Paragraph<'a> {
-        let level = self.score.level();
-        if level < 15 {
-            Paragraph::new(Spans::from(Span::styled(
-                format!("{}", self.score.level()),
-                Style::default().add_modifier(Modifier::BOLD),
-            )))
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    // Whether the piece can be controlled, nothing happens to it once the game is over or while it's paused
+    fn is_playing(&self) -> bool {
+        self.outcome.is_none() && !self.is_paused()
+    }
+
+    // Game time, the clock's time minus however long the game spent paused
+    fn now(&self) -> Duration {
+        self.paused_at.unwrap_or(self.clock_time) - self.paused_total
+    }
+
+    // Advance the game to the clock's current time, applying gravity for every step that's due
+    pub fn update(&mut self) {
+        self.record(Input::Tick);
+        if !self.is_playing() {
+            return;
+        }
+
+        if let GameMode::Ultra { duration } = self.mode {
+            if self.elapsed() >= duration {
+                self.end(Outcome::TimeUp);
+                self.end_time = Some(self.start_time + duration);
+                return;
+            }
+        }
+
+        self.update_autoshift();
+
+        // Soft drop replaces gravity while it's held, unless it's instant and the piece is already down
+        // An SDF so high that the soft drop delay rounds down to nothing is as good as instant
+        let gravity = self.score.level.get_tick_delay();
+        let instant = self.handling.sdf == 0 || (gravity / self.handling.sdf).is_zero();
+        let fast_drop = self.soft_dropping && !instant;
+        if self.soft_dropping && instant {
+            self.soft_drop_to_floor();
+        }
+
+        let delay = if fast_drop {
+            gravity / self.handling.sdf
         } else {
-            Paragraph::new(Spans::from(Span::styled(
-                format!("{}", self.score.level()),
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .fg(Color::LightRed),
-            )))
+            gravity
+        };
+        while self.outcome.is_none() && self.now() - self.last_gravity >= delay {
+            self.last_gravity += delay;
+            let fell = if fast_drop {
+                self.soft_drop()
+            } else {
+                self.move_down()
+            };
+            // Once the piece is resting the rest of the steps due would do nothing, so skip past them
+            if !fell {
+                let behind = (self.now() - self.last_gravity).as_nanos() / delay.as_nanos();
+                self.last_gravity += Duration::from_nanos((behind * delay.as_nanos()) as u64);
+            }
+        }
+
+        self.update_lock();
+    }
+
+    // Lock the piece once it's been resting for the lock delay, which runs separately from gravity
+    fn update_lock(&mut self) {
+        if self.outcome.is_some() {
+            return;
+        }
+        // The delay keeps running while the piece is kicked up off the floor, so it can't be restarted by
+        // landing again, only by the piece's lock reset policy
+        if !self.is_locking() {
+            return;
+        }
+
+        let now = self.now();
+        let since = *self.lock_since.get_or_insert(now);
+        if now - since >= Self::LOCK_DELAY {
+            self.lock_piece();
+        }
+    }
+
+    // Called after the piece successfully moves, rotates or falls to decide whether the lock delay starts over
+    // Reaching a new lowest row always restarts it, falling back to a row the piece already reached never does
+    fn moved(&mut self, fell: bool) {
+        if self.piece_offset.0 < self.lowest_row {
+            self.lowest_row = self.piece_offset.0;
+            self.lock_resets = 0;
+            self.lock_since = None;
+        } else if !fell && self.lock_since.is_some() {
+            match self.lock_reset {
+                LockReset::Infinite => self.lock_since = Some(self.now()),
+                LockReset::Move if self.lock_resets < LockReset::MOVE_LIMIT => {
+                    self.lock_resets += 1;
+                    self.lock_since = Some(self.now());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Start holding an input, it acts once straight away and then keeps going in update until released
+    pub fn press(&mut self, input: HeldInput) {
+        self.record(Input::Press(input));
+        if !self.is_playing() {
+            return;
+        }
+        let now = self.now();
+        match input {
+            HeldInput::Left => {
+                self.held_left = true;
+                self.autoshift = Some(Autoshift::new(-1, now));
+                self.move_left();
+            }
+            HeldInput::Right => {
+                self.held_right = true;
+                self.autoshift = Some(Autoshift::new(1, now));
+                self.move_right();
+            }
+            HeldInput::SoftDrop => {
+                self.soft_dropping = true;
+                self.last_gravity = now;
+                if self.handling.sdf == 0 {
+                    self.soft_drop_to_floor();
+                } else {
+                    self.soft_drop();
+                }
+            }
         }
     }
 
-    pub fn lines_paragraph(&self) -> Paragraph<'a> {
-        if self.score.level() >= 15 {
-            Paragraph::new(Spans::from(Span::styled(
-                format!("{}", self.score.lines()),
-                Style::default().add_modifier(Modifier::BOLD),
-            )))
+    // Letting go of one direction while the other is still held starts charging DAS for the other
+    pub fn release(&mut self, input: HeldInput) {
+        self.record(Input::Release(input));
+        let now = self.now();
+        match input {
+            HeldInput::Left => {
+                self.held_left = false;
+                if matches!(self.autoshift, Some(shift) if shift.direction == -1) {
+                    self.autoshift = self.held_right.then(|| Autoshift::new(1, now));
+                }
+            }
+            HeldInput::Right => {
+                self.held_right = false;
+                if matches!(self.autoshift, Some(shift) if shift.direction == 1) {
+                    self.autoshift = self.held_left.then(|| Autoshift::new(-1, now));
+                }
+            }
+            HeldInput::SoftDrop => self.soft_dropping = false,
+        }
+    }
+
+    // Repeat the held direction once DAS has charged, one move per ARR or all the way with an ARR of zero
+    fn update_autoshift(&mut self) {
+        let Some(mut shift) = self.autoshift else {
+            return;
+        };
+        let held_for = self.now() - shift.since;
+        if held_for < self.handling.das {
+            return;
+        }
+
+        if self.handling.arr.is_zero() {
+            while self.shift(shift.direction) {}
         } else {
-            Paragraph::new(Spans::from(Span::styled(
-                format!("{}->{}", self.score.lines(), self.score.lines_goal()),
-                Style::default().add_modifier(Modifier::BOLD),
-            )))
+            let due = ((held_for - self.handling.das).as_nanos() / self.handling.arr.as_nanos())
+                as u32
+                + 1;
+            // Once the piece hits something the rest of the repeats due would do nothing, so skip past them
+            while shift.repeats < due {
+                if !self.shift(shift.direction) {
+                    shift.repeats = due;
+                    break;
+                }
+                shift.repeats += 1;
+            }
         }
+
+        self.autoshift = Some(shift);
     }
 
     pub fn is_locking(&self) -> bool {
         self.piece_offset.0 == self.ghost_offset.0
     }
 
-    pub fn move_left(&mut self) {
-        let new_offset = (
-            self.piece_offset.0 as isize,
-            self.piece_offset.1 as isize - 1,
-        );
-        if self.try_move(new_offset, self.cur_rotation) {
+    fn move_left(&mut self) {
+        self.shift(-1);
+    }
+
+    fn move_right(&mut self) {
+        self.shift(1);
+    }
+
+    // Move the piece a column left (-1) or right (1), returns whether it moved
+    fn shift(&mut self, direction: isize) -> bool {
+        let new_offset = (self.piece_offset.0, self.piece_offset.1 + direction);
+        let moved = self.try_move(new_offset, self.cur_rotation);
+        if moved {
             self.piece_offset.1 = new_offset.1;
-            self.last_move = Instant::now();
+            self.last_kick = None;
+            self.update_ghost_position();
+            self.moved(false);
         }
-        self.update_ghost_position();
+        moved
     }
 
-    pub fn move_right(&mut self) {
-        let new_offset = (
-            self.piece_offset.0 as isize,
-            self.piece_offset.1 as isize + 1,
-        );
-        if self.try_move(new_offset, self.cur_rotation) {
-            self.piece_offset.1 = new_offset.1;
-            self.last_move = Instant::now();
+    // Soft drop points are only given for cells the piece actually fell, returns whether it did
+    fn soft_drop(&mut self) -> bool {
+        let moved = self.move_down();
+        if moved {
+            self.score.do_event(ScoreEvent::SoftDrop(1));
         }
-        self.update_ghost_position();
+        moved
     }
 
-    pub fn soft_drop(&mut self) {
-        self.move_down();
-        self.score.do_event(ScoreEvent::SoftDrop(1));
+    // Soft drop with an instant SDF, the piece goes to the floor but doesn't lock like a hard drop
+    fn soft_drop_to_floor(&mut self) {
+        let dist = self.piece_offset.0 - self.ghost_offset.0;
+        if dist > 0 {
+            self.score.do_event(ScoreEvent::SoftDrop(dist));
+            self.piece_offset = self.ghost_offset;
+            self.last_kick = None;
+            self.moved(true);
+        }
     }
 
-    pub fn move_down(&mut self) {
+    // Returns whether the piece moved down, locking is left to the lock delay
+    fn move_down(&mut self) -> bool {
         let new_offset = (self.piece_offset.0 - 1, self.piece_offset.1);
-        if self.try_move(new_offset, self.cur_rotation) {
+        let moved = self.try_move(new_offset, self.cur_rotation);
+        if moved {
             self.piece_offset.0 = new_offset.0;
-            self.last_move = Instant::now();
-        } else if self.last_move.elapsed().as_millis() > 500 {
-            self.lock_piece();
+            self.last_kick = None;
+            self.moved(true);
         }
+        moved
     }
 
     pub fn rotate_left(&mut self) {
+        self.record(Input::RotateLeft);
+        if !self.is_playing() {
+            return;
+        }
         let new_rotation = (self.cur_rotation + 3) % 4;
 
         if self.try_rotate_with_kick(new_rotation) {
-            self.last_move = Instant::now();
+            self.update_ghost_position();
+            self.moved(false);
         }
-
-        self.update_ghost_position();
     }
 
     pub fn rotate_right(&mut self) {
+        self.record(Input::RotateRight);
+        if !self.is_playing() {
+            return;
+        }
         let new_rotation = (self.cur_rotation + 1) % 4;
 
         if self.try_rotate_with_kick(new_rotation) {
-            self.last_move = Instant::now();
+            self.update_ghost_position();
+            self.moved(false);
         }
+    }
 
-        self.update_ghost_position();
+    pub fn rotate_180(&mut self) {
+        self.record(Input::Rotate180);
+        if !self.is_playing() {
+            return;
+        }
+        let new_rotation = (self.cur_rotation + 2) % 4;
+
+        if self.try_rotate_with_kick(new_rotation) {
+            self.update_ghost_position();
+            self.moved(false);
+        }
     }
 
     pub fn hard_drop(&mut self) {
+        self.record(Input::HardDrop);
+        if !self.is_playing() {
+            return;
+        }
         let dist = self.piece_offset.0 - self.ghost_offset.0;
         self.score.do_event(ScoreEvent::HardDrop(dist));
 
+        if dist > 0 {
+            self.last_kick = None;
+        }
         self.piece_offset = self.ghost_offset;
         self.lock_piece();
     }
 
     pub fn hold(&mut self) {
-        if !self.can_hold {
+        self.record(Input::Hold);
+        if !self.is_playing() || !self.can_hold {
             return;
         }
 
@@ -265,39 +656,110 @@ impl<'a> Game<'a> {
         self.can_hold = false;
     }
 
-    fn is_tspin(&self) -> bool {
-        let n_corners = self.cell_occupied(self.piece_offset.0, self.piece_offset.1) as u8
-            + self.cell_occupied(self.piece_offset.0 - 2, self.piece_offset.1) as u8
-            + self.cell_occupied(self.piece_offset.0, self.piece_offset.1 + 2) as u8
-            + self.cell_occupied(self.piece_offset.0 - 2, self.piece_offset.1 + 2) as u8;
+    // Guideline T-spin check, made when the piece locks
+    // The last move has to be a rotation and three of the four corners around the T's centre have to be filled,
+    // it's a full T-spin if both corners it points towards are filled or it got there with a TST or fin kick
+    fn tspin(&self) -> TSpins {
+        let kick = match self.last_kick {
+            Some(kick) if self.cur_piece.kind == PieceKind::T => kick,
+            _ => return TSpins::None,
+        };
+
+        let shape = self.current_shape();
+        let filled = |(i, j): (isize, isize)| {
+            (0..4).contains(&i) && (0..4).contains(&j) && shape[i as usize][j as usize] != 0
+        };
+        let sides = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+        let cells = (0..4).flat_map(|i| (0..4).map(move |j| (i, j)));
+
+        // The centre is the only cell with three neighbours, and the one it points towards has nothing opposite it
+        let center = match cells.filter(|&cell| filled(cell)).find(|&(i, j)| {
+            sides
+                .iter()
+                .filter(|(di, dj)| filled((i + di, j + dj)))
+                .count()
+                == 3
+        }) {
+            Some(center) => center,
+            None => return TSpins::None,
+        };
+        let facing = sides
+            .iter()
+            .find(|(di, dj)| {
+                filled((center.0 + di, center.1 + dj)) && !filled((center.0 - di, center.1 - dj))
+            })
+            .copied()
+            .unwrap_or((0, 0));
+
+        let mut corners = 0;
+        let mut front_corners = 0;
+        for (di, dj) in [(-1, -1), (-1, 1), (1, -1), (1, 1)] {
+            let row_idx = self.piece_offset.0 - (center.0 + di);
+            let col_idx = self.piece_offset.1 + center.1 + dj;
+            if self.cell_occupied(row_idx, col_idx) {
+                corners += 1;
+                if di * facing.0 + dj * facing.1 > 0 {
+                    front_corners += 1;
+                }
+            }
+        }
+
+        if corners < 3 {
+            TSpins::None
+        } else if front_corners == 2 || kick.is_tst_or_fin() {
+            TSpins::TSpin
+        } else {
+            TSpins::MiniTSpin
+        }
+    }
+
+    // Spin for the piece about to lock, T-spins always count and other pieces count under all-spin rules
+    // if they got stuck by rotating, unable to move left, right or up
+    fn spin(&self) -> Option<ScoreEvent> {
+        match self.cur_piece.kind {
+            PieceKind::T => match self.tspin() {
+                TSpins::None => None,
+                spin => Some(ScoreEvent::TSpin(spin)),
+            },
+            PieceKind::O => None,
+            kind if self.last_kick.is_some() && self.is_immobile() => match self.spin_rule {
+                SpinRule::TOnly => None,
+                SpinRule::AllMini => Some(ScoreEvent::Spin(kind, TSpins::MiniTSpin)),
+                SpinRule::AllSpin => Some(ScoreEvent::Spin(kind, TSpins::TSpin)),
+            },
+            _ => None,
+        }
+    }
 
-        n_corners >= 3
+    fn is_immobile(&self) -> bool {
+        let (row, col) = self.piece_offset;
+        !self.try_move((row, col - 1), self.cur_rotation)
+            && !self.try_move((row, col + 1), self.cur_rotation)
+            && !self.try_move((row + 1, col), self.cur_rotation)
     }
 
     fn try_rotate_with_kick(&mut self, new_rotation: u8) -> bool {
-        if self.try_move(self.piece_offset, new_rotation) {
-            self.cur_rotation = new_rotation;
-
-            // Check for T-Spin
-            if *self.cur_piece == Piece::T && self.is_tspin() {
-                self.score.do_event(ScoreEvent::TSpin(TSpins::TSpin));
+        let half_turn = (new_rotation + 4 - self.cur_rotation) % 4 == 2;
+        let blocked = match self.first_blocked(self.piece_offset, new_rotation) {
+            Some(blocked) => blocked,
+            None => {
+                self.cur_rotation = new_rotation;
+                self.last_kick = Some(Kick { test: 0, half_turn });
+                return true;
             }
+        };
 
-            return true;
-        }
+        let kicks = self
+            .rotation
+            .kicks(self.cur_piece, self.cur_rotation, new_rotation, blocked);
 
         let mut new_offset;
-        for (j, i) in self.cur_piece.kicks[self.cur_rotation as usize][new_rotation as usize] {
+        for (test, &(j, i)) in (1..).zip(kicks) {
             new_offset = (self.piece_offset.0 + i, self.piece_offset.1 + j);
             if self.try_move(new_offset, new_rotation) {
                 self.piece_offset = new_offset;
                 self.cur_rotation = new_rotation;
-
-                // Check for Mini T-Spin
-                if *self.cur_piece == Piece::T && self.is_tspin() {
-                    self.score.do_event(ScoreEvent::TSpin(TSpins::MiniTSpin));
-                }
-
+                self.last_kick = Some(Kick { test, half_turn });
                 return true;
             }
         }
@@ -308,21 +770,21 @@ impl<'a> Game<'a> {
     fn reset_piece(&mut self, use_next_piece: bool) {
         if use_next_piece {
             self.cur_piece = self.next_piece;
-
-            if self.piece_bag.len() < 7 {
-                self.piece_bag
-                    .append(&mut VecDeque::from(Piece::random_bag()));
-            }
-            self.next_piece = self.piece_bag.pop_front().unwrap();
+            self.next_piece = self.piece_queue.pop_front().unwrap();
+            self.piece_queue.push_back(self.randomizer.next_piece());
         }
 
-        self.piece_offset = (21, 3);
+        self.piece_offset = self.rotation.spawn_offset();
         self.cur_rotation = 0;
+        self.last_gravity = self.now();
+        self.lock_since = None;
+        self.lock_resets = 0;
+        self.lowest_row = self.piece_offset.0;
+        self.last_kick = None;
 
-        // Lose condition
+        // Block out, the new piece has nowhere to go
         if !self.try_move(self.piece_offset, self.cur_rotation) {
-            self.board.reset();
-            self.score.save_and_reset_score();
+            self.end(Outcome::BlockOut);
         }
 
         self.update_ghost_position();
@@ -367,6 +829,14 @@ impl<'a> Game<'a> {
             4 => self.score.do_event(ScoreEvent::LineClear(Lines::Tetris)),
             _ => {}
         }
+        if n_lines > 0
+            && new_board
+                .0
+                .iter()
+                .all(|row| row.iter().all(|&cell| cell == BoardColor::Empty))
+        {
+            self.score.do_event(ScoreEvent::PerfectClear);
+        }
 
         self.score.do_event(ScoreEvent::EndTurn);
 
@@ -374,13 +844,19 @@ impl<'a> Game<'a> {
     }
 
     fn update_score_log(&mut self) {
-        while self.score_log.len() >= 7 {
+        self.score_log.push_back(LogEntry {
+            text: self.score.last_turn_text().to_string(),
+            highlight: self.score.highlight(),
+        });
+        if self.score.last_turn_perfect_clear() {
+            self.score_log.push_back(LogEntry {
+                text: "PERFECT CLEAR".to_string(),
+                highlight: Highlight::PerfectClear,
+            });
+        }
+        while self.score_log.len() > 7 {
             self.score_log.pop_front();
         }
-        self.score_log.push_back(Spans::from(Span::styled(
-            self.score.last_turn_text().to_string(),
-            Style::default().fg(self.score.text_color()),
-        )));
     }
 
     fn cell_occupied(&self, row_idx: isize, col_idx: isize) -> bool {
@@ -392,38 +868,288 @@ impl<'a> Game<'a> {
     }
 
     fn try_move(&self, offset: (isize, isize), rotation: u8) -> bool {
-        for (i, row) in self.cur_piece.shapes[rotation as usize].iter().enumerate() {
+        self.first_blocked(offset, rotation).is_none()
+    }
+
+    // Row and column within the shape of the first cell that overlaps something, reading from the top left
+    fn first_blocked(&self, offset: (isize, isize), rotation: u8) -> Option<(usize, usize)> {
+        let shape = self.rotation.shape(self.cur_piece, rotation);
+        for (i, row) in shape.iter().enumerate() {
             let row_idx = offset.0 - i as isize;
             for (j, cell) in row.iter().enumerate() {
                 let col_idx = offset.1 + j as isize;
                 if *cell != 0 && self.cell_occupied(row_idx, col_idx) {
-                    return false;
+                    return Some((i, j));
                 }
             }
         }
-        true
+        None
+    }
+
+    fn current_shape(&self) -> &'static [[u8; 4]; 4] {
+        self.rotation.shape(self.cur_piece, self.cur_rotation)
     }
 
     fn lock_piece(&mut self) {
-        for (i, row) in self.cur_piece.shapes[self.cur_rotation as usize]
-            .iter()
-            .enumerate()
-        {
+        // Spins have to be checked before the piece becomes part of the board
+        let spin = self.spin();
+
+        let mut visible = false;
+        for (i, row) in self.current_shape().iter().enumerate() {
             let row_idx = self.piece_offset.0 - i as isize;
             for (j, cell) in row.iter().enumerate() {
                 let col_idx = self.piece_offset.1 + j as isize;
                 if *cell != 0 {
                     self.board.0[row_idx as usize][col_idx as usize] = self.cur_piece.color;
+                    visible |= row_idx < Game::DISPLAY_HEIGHT as isize;
                 }
             }
         }
+        self.pieces += 1;
 
-        self.clear_lines();
+        // Lock out, the whole piece ended up above the visible board
+        if !visible {
+            self.end(Outcome::LockOut);
+            return;
+        }
 
-        self.reset_piece(true);
+        if let Some(event) = spin {
+            self.score.do_event(event);
+        }
+
+        self.clear_lines();
 
         self.update_score_log();
 
         self.can_hold = true;
+
+        if self.goal_reached() {
+            self.end(Outcome::GoalReached);
+            return;
+        }
+
+        self.reset_piece(true);
+    }
+
+    fn goal_reached(&self) -> bool {
+        match self.mode {
+            GameMode::Marathon | GameMode::Ultra { .. } => false,
+            GameMode::Sprint { lines } => self.score.cleared_lines() >= lines,
+        }
+    }
+
+    fn end(&mut self, outcome: Outcome) {
+        self.outcome = Some(outcome);
+        self.end_time = Some(self.now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::ManualClock;
+
+    const STEP: Duration = Duration::from_millis(100);
+
+    fn game(lock_reset: LockReset) -> (Game, ManualClock) {
+        let clock = ManualClock::new();
+        let config = GameConfig {
+            seed: Some(0),
+            lock_reset,
+            ..GameConfig::default()
+        };
+        let game = Game::with_config(config, Box::new(clock.clone()));
+        assert_ne!(game.current_piece().kind, PieceKind::O);
+        (game, clock)
+    }
+
+    // Let the first piece fall until it's resting on the floor of the empty board
+    fn land(game: &mut Game, clock: &ManualClock) {
+        while !game.is_locking() {
+            clock.advance(STEP);
+            game.update();
+        }
+    }
+
+    // Rotate the piece back and forth on the floor, returns how long it lasted or None if it never locked
+    fn spin_until_locked(game: &mut Game, clock: &ManualClock) -> Option<Duration> {
+        let landed = clock.now();
+        for i in 0..200 {
+            clock.advance(STEP);
+            game.update();
+            if i % 2 == 0 {
+                game.rotate_left();
+            } else {
+                game.rotate_right();
+            }
+            if game.pieces() > 0 {
+                return Some(clock.now() - landed);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn resting_piece_locks_after_lock_delay() {
+        let (mut game, clock) = game(LockReset::Move);
+        land(&mut game, &clock);
+        clock.advance(Game::LOCK_DELAY - Duration::from_millis(1));
+        game.update();
+        assert_eq!(game.pieces(), 0);
+        clock.advance(Duration::from_millis(1));
+        game.update();
+        assert_eq!(game.pieces(), 1);
+    }
+
+    #[test]
+    fn infinite_reset_never_locks_a_spinning_piece() {
+        let (mut game, clock) = game(LockReset::Infinite);
+        land(&mut game, &clock);
+        assert_eq!(spin_until_locked(&mut game, &clock), None);
+    }
+
+    #[test]
+    fn move_reset_locks_once_out_of_resets() {
+        let (mut game, clock) = game(LockReset::Move);
+        land(&mut game, &clock);
+        // Shuffle left and right along the floor, so every move is a reset and never a new lowest row
+        let shuffle = |game: &mut Game, i: u32| {
+            let input = [HeldInput::Left, HeldInput::Right][i as usize % 2];
+            game.press(input);
+            game.release(input);
+        };
+        for i in 0..LockReset::MOVE_LIMIT {
+            clock.advance(STEP);
+            game.update();
+            shuffle(&mut game, i);
+        }
+
+        // The last reset still counts
+        clock.advance(Game::LOCK_DELAY - Duration::from_millis(1));
+        game.update();
+        assert_eq!(game.pieces(), 0);
+
+        // The next move doesn't reset, so the delay from the last reset runs out
+        shuffle(&mut game, LockReset::MOVE_LIMIT);
+        clock.advance(Duration::from_millis(1));
+        game.update();
+        assert_eq!(game.pieces(), 1);
+    }
+
+    #[test]
+    fn step_reset_locks_a_spinning_piece() {
+        let (mut game, clock) = game(LockReset::Step);
+        land(&mut game, &clock);
+        let lasted = spin_until_locked(&mut game, &clock).unwrap();
+        assert!(lasted <= Game::LOCK_DELAY + game.score.level.get_tick_delay());
+    }
+
+    #[test]
+    fn paused_game_ignores_inputs() {
+        let (mut game, clock) = game(LockReset::Move);
+        game.pause();
+        let position = game.piece_position();
+        game.rotate_right();
+        game.press(HeldInput::Left);
+        game.hard_drop();
+        game.hold();
+        clock.advance(Duration::from_secs(5));
+        game.update();
+        assert_eq!(game.piece_position(), position);
+        assert_eq!(game.pieces(), 0);
+        assert_eq!(game.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn start_level_is_clamped() {
+        for (level, clamped) in [(0, 1), (20, Level::MAX)] {
+            let config = GameConfig {
+                level,
+                ..GameConfig::default()
+            };
+            let game = Game::with_config(config, Box::new(ManualClock::new()));
+            assert_eq!(game.start_level(), clamped);
+            assert_eq!(game.score.level(), clamped);
+        }
+    }
+
+    // Put a T at the given position and rotation with the given cells filled around it
+    fn t_setup(rotation: u8, offset: (isize, isize), filled: &[(usize, usize)]) -> Game {
+        let (mut game, _) = game(LockReset::Move);
+        game.cur_piece = &Piece::T;
+        game.cur_rotation = rotation;
+        game.piece_offset = offset;
+        for &(row, col) in filled {
+            game.board.0[row][col] = BoardColor::Red;
+        }
+        game
+    }
+
+    #[test]
+    fn fifth_quarter_turn_kick_upgrades_to_a_full_tspin() {
+        let mut game = t_setup(0, (4, 3), &[(2, 2), (0, 2), (2, 4), (4, 3)]);
+        game.rotate_right();
+        assert_eq!(game.piece_position(), ((2, 2), 1));
+        assert!(matches!(game.tspin(), TSpins::TSpin));
+    }
+
+    #[test]
+    fn half_turn_kick_does_not_upgrade_a_mini_tspin() {
+        let mut game = t_setup(1, (2, 0), &[(1, 0), (0, 2), (4, 1), (4, 3), (2, 3)]);
+        game.rotate_180();
+        assert_eq!(game.piece_position(), ((4, 1), 3));
+        assert!(matches!(game.tspin(), TSpins::MiniTSpin));
+    }
+
+    #[test]
+    fn handling_timing() {
+        let clock = ManualClock::new();
+        let config = GameConfig {
+            seed: Some(0),
+            handling: Handling {
+                das: Duration::from_millis(100),
+                arr: Duration::from_millis(20),
+                sdf: 10,
+            },
+            ..GameConfig::default()
+        };
+        let mut game = Game::with_config(config, Box::new(clock.clone()));
+        let ((row, col), _) = game.piece_position();
+        let tick = |game: &mut Game, ms| {
+            clock.advance(Duration::from_millis(ms));
+            game.update();
+            game.piece_position().0
+        };
+
+        // One move on press, the next once DAS has charged, then one every ARR
+        game.press(HeldInput::Right);
+        assert_eq!(tick(&mut game, 99), (row, col + 1));
+        assert_eq!(tick(&mut game, 1), (row, col + 2));
+        assert_eq!(tick(&mut game, 19), (row, col + 2));
+        assert_eq!(tick(&mut game, 1), (row, col + 3));
+        game.release(HeldInput::Right);
+
+        // Soft drop moves a row straight away, then falls ten times faster than the level 1 gravity of a second
+        game.press(HeldInput::SoftDrop);
+        assert_eq!(tick(&mut game, 99), (row - 1, col + 3));
+        assert_eq!(tick(&mut game, 1), (row - 2, col + 3));
+        assert_eq!(tick(&mut game, 200), (row - 4, col + 3));
+    }
+
+    #[test]
+    fn sdf_too_fast_for_gravity_is_instant() {
+        let clock = ManualClock::new();
+        let config = GameConfig {
+            handling: Handling {
+                sdf: u32::MAX,
+                ..Handling::default()
+            },
+            ..GameConfig::default()
+        };
+        let mut game = Game::with_config(config, Box::new(clock.clone()));
+        game.press(HeldInput::SoftDrop);
+        clock.advance(Duration::from_millis(16));
+        game.update();
+        assert!(game.is_locking());
     }
 }
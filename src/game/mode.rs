@@ -0,0 +1,71 @@
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+// The different ways a game can be played, and what ends them
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum GameMode {
+    // Endless, the level goes up as lines are cleared
+    #[default]
+    Marathon,
+    // Clear a set number of lines as fast as possible
+    Sprint {
+        lines: u32,
+    },
+    // Score as much as possible before the time runs out, at a gravity that never changes
+    Ultra {
+        duration: Duration,
+    },
+}
+
+impl GameMode {
+    pub const SPRINT_LINES: u32 = 40;
+    pub const ULTRA_DURATION: Duration = Duration::from_secs(120);
+
+    pub fn sprint() -> Self {
+        GameMode::Sprint {
+            lines: Self::SPRINT_LINES,
+        }
+    }
+
+    pub fn ultra() -> Self {
+        GameMode::Ultra {
+            duration: Self::ULTRA_DURATION,
+        }
+    }
+}
+
+impl Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameMode::Marathon => write!(f, "Marathon"),
+            GameMode::Sprint { lines } => write!(f, "Sprint {lines}L"),
+            GameMode::Ultra { duration } => write!(f, "Ultra {}s", duration.as_secs()),
+        }
+    }
+}
+
+// Why a game stopped
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    // The mode's goal was reached, like clearing all the lines in a sprint
+    GoalReached,
+    // The mode's time limit ran out, like at the end of an ultra
+    TimeUp,
+    // A new piece spawned overlapping the stack
+    BlockOut,
+    // A piece locked entirely above the visible part of the board
+    LockOut,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::GoalReached => write!(f, "Finished"),
+            Outcome::TimeUp => write!(f, "Time Up"),
+            Outcome::BlockOut => write!(f, "Block Out"),
+            Outcome::LockOut => write!(f, "Lock Out"),
+        }
+    }
+}
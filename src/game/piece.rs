@@ -1,18 +1,36 @@
-use rand::seq::SliceRandom;
-use tui::{
-    style::{Color, Style},
-    text::{Span, Spans},
-};
-
 // Struct to store constant piece data including its shape at every rotation, piece color, and kick offsets
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Piece {
+    pub kind: PieceKind,
+    // Shapes and kicks here are SRS, other rotation systems bring their own
     pub shapes: [[[u8; 4]; 4]; 4],
     pub color: BoardColor,
     pub kicks: [[[(isize, isize); 4]; 4]; 4],
+    // Kicks for turning 180 degrees from each rotation, which the SRS tables above don't cover
+    pub kicks_180: [[(isize, isize); 5]; 4],
 }
 
-use super::colors::{self, BoardColor};
+use std::fmt::{self, Display};
+
+use super::colors::BoardColor;
+
+// Which of the seven tetrominoes a piece is
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PieceKind {
+    O,
+    I,
+    S,
+    Z,
+    T,
+    L,
+    J,
+}
+
+impl Display for PieceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
 
 impl Piece {
     // Kick data from here https://tetris.fandom.com/wiki/SRS#Wall_Kicks
@@ -72,8 +90,19 @@ impl Piece {
         ],
     ];
 
+    const O_KICKS_180: [[(isize, isize); 5]; 4] = [[(0, 0); 5]; 4];
+
+    // SRS+ 180 kicks as used by TETR.IO, the same for every piece but O
+    const KICKS_180: [[(isize, isize); 5]; 4] = [
+        [(0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+        [(1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+        [(0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+        [(-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+    ];
+
     // Piece shape data from here https://tetris.fandom.com/wiki/SRS?file=SRS-pieces.png
     pub const O: Piece = Piece {
+        kind: PieceKind::O,
         shapes: [
             [[0, 1, 1, 0], [0, 1, 1, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
             [[0, 1, 1, 0], [0, 1, 1, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
@@ -82,8 +111,10 @@ impl Piece {
         ],
         color: BoardColor::Yellow,
         kicks: Self::O_KICKS,
+        kicks_180: Self::O_KICKS_180,
     };
     pub const I: Piece = Piece {
+        kind: PieceKind::I,
         shapes: [
             [[0, 0, 0, 0], [1, 1, 1, 1], [0, 0, 0, 0], [0, 0, 0, 0]],
             [[0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0]],
@@ -92,8 +123,10 @@ impl Piece {
         ],
         color: BoardColor::LightBlue,
         kicks: Self::I_KICKS,
+        kicks_180: Self::KICKS_180,
     };
     pub const S: Piece = Piece {
+        kind: PieceKind::S,
         shapes: [
             [[0, 1, 1, 0], [1, 1, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
             [[0, 1, 0, 0], [0, 1, 1, 0], [0, 0, 1, 0], [0, 0, 0, 0]],
@@ -102,8 +135,10 @@ impl Piece {
         ],
         color: BoardColor::Red,
         kicks: Self::NORMAL_KICKS,
+        kicks_180: Self::KICKS_180,
     };
     pub const Z: Piece = Piece {
+        kind: PieceKind::Z,
         shapes: [
             [[1, 1, 0, 0], [0, 1, 1, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
             [[0, 0, 1, 0], [0, 1, 1, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
@@ -112,8 +147,10 @@ impl Piece {
         ],
         color: BoardColor::Green,
         kicks: Self::NORMAL_KICKS,
+        kicks_180: Self::KICKS_180,
     };
     pub const T: Piece = Piece {
+        kind: PieceKind::T,
         shapes: [
             [[0, 1, 0, 0], [1, 1, 1, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
             [[0, 1, 0, 0], [0, 1, 1, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
@@ -122,8 +159,10 @@ impl Piece {
         ],
         color: BoardColor::Purple,
         kicks: Self::NORMAL_KICKS,
+        kicks_180: Self::KICKS_180,
     };
     pub const L: Piece = Piece {
+        kind: PieceKind::L,
         shapes: [
             [[0, 0, 1, 0], [1, 1, 1, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
             [[0, 1, 0, 0], [0, 1, 0, 0], [0, 1, 1, 0], [0, 0, 0, 0]],
@@ -132,8 +171,10 @@ impl Piece {
         ],
         color: BoardColor::Orange,
         kicks: Self::NORMAL_KICKS,
+        kicks_180: Self::KICKS_180,
     };
     pub const J: Piece = Piece {
+        kind: PieceKind::J,
         shapes: [
             [[1, 0, 0, 0], [1, 1, 1, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
             [[0, 1, 1, 0], [0, 1, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
@@ -142,6 +183,7 @@ impl Piece {
         ],
         color: BoardColor::DarkBlue,
         kicks: Self::NORMAL_KICKS,
+        kicks_180: Self::KICKS_180,
     };
 
     pub const ALL: [&'static Piece; 7] = [
@@ -153,47 +195,4 @@ impl Piece {
         &Self::L,
         &Self::J,
     ];
-
-    pub fn random_bag<'a>() -> [&'a Piece; 7] {
-        // Ensures an even distribution of pieces by giving one of each type per 7
-        let mut permutation = Piece::ALL;
-        permutation.shuffle(&mut rand::thread_rng());
-
-        permutation
-
-        // [&Piece::I; 7]
-    }
-}
-
-impl<'a> From<&Piece> for Vec<Spans<'a>> {
-    fn from(piece: &Piece) -> Self {
-        let mut text = vec![Spans::from(Span::styled(
-            "",
-            Style::default().fg(Color::Gray),
-        ))];
-
-        piece.shapes[0].iter().take(2).for_each(|row| {
-            let mut text_row = Vec::<Span>::new();
-
-            match piece {
-                &Piece::O | &Piece::I => {}
-                _ => {
-                    // Add a half block of space to center odd width blocks
-                    text_row.push(Span::raw(" "));
-                }
-            }
-
-            row.iter().for_each(|cell| {
-                text_row.push(if *cell != 0 {
-                    colors::cell_to_span("██", piece.color)
-                } else {
-                    Span::raw("  ")
-                });
-            });
-
-            text.push(Spans::from(text_row));
-        });
-
-        text
-    }
 }
@@ -0,0 +1,162 @@
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+};
+
+use super::piece::Piece;
+
+// Something that decides which piece comes next
+pub trait Randomizer {
+    fn next_piece(&mut self) -> &'static Piece;
+}
+
+// The available randomizers, used to pick one for a game
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum RandomizerKind {
+    #[default]
+    SevenBag,
+    FourteenBag,
+    Random,
+    Nes,
+    Tgm,
+}
+
+impl RandomizerKind {
+    // Iterator over all available randomizers
+    pub fn iterator() -> std::slice::Iter<'static, RandomizerKind> {
+        static KINDS: [RandomizerKind; 5] = [
+            RandomizerKind::SevenBag,
+            RandomizerKind::FourteenBag,
+            RandomizerKind::Random,
+            RandomizerKind::Nes,
+            RandomizerKind::Tgm,
+        ];
+        KINDS.iter()
+    }
+
+    pub fn build(&self, seed: u64) -> Box<dyn Randomizer> {
+        let rng = ChaCha8Rng::seed_from_u64(seed);
+        match self {
+            RandomizerKind::SevenBag => Box::new(Bag::new(rng, 1)),
+            RandomizerKind::FourteenBag => Box::new(Bag::new(rng, 2)),
+            RandomizerKind::Random => Box::new(PureRandom { rng }),
+            RandomizerKind::Nes => Box::new(Nes { rng, last: None }),
+            RandomizerKind::Tgm => Box::new(Tgm::new(rng)),
+        }
+    }
+}
+
+impl Display for RandomizerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RandomizerKind::SevenBag => write!(f, "7-Bag"),
+            RandomizerKind::FourteenBag => write!(f, "14-Bag"),
+            RandomizerKind::Random => write!(f, "Random"),
+            RandomizerKind::Nes => write!(f, "NES"),
+            RandomizerKind::Tgm => write!(f, "TGM"),
+        }
+    }
+}
+
+// Deals out shuffled bags holding every piece a fixed number of times
+pub struct Bag {
+    rng: ChaCha8Rng,
+    copies: usize,
+    bag: Vec<&'static Piece>,
+}
+
+impl Bag {
+    pub fn new(rng: ChaCha8Rng, copies: usize) -> Self {
+        Bag {
+            rng,
+            copies,
+            bag: Vec::new(),
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next_piece(&mut self) -> &'static Piece {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend(Piece::ALL);
+            }
+            self.bag.shuffle(&mut self.rng);
+        }
+        self.bag.pop().unwrap()
+    }
+}
+
+// Every piece is equally likely every time, with no memory at all
+pub struct PureRandom {
+    rng: ChaCha8Rng,
+}
+
+impl Randomizer for PureRandom {
+    fn next_piece(&mut self) -> &'static Piece {
+        Piece::ALL.choose(&mut self.rng).unwrap()
+    }
+}
+
+// NES style, rolls an 8 sided die and rerolls once if it lands on the extra side or repeats the last piece
+pub struct Nes {
+    rng: ChaCha8Rng,
+    last: Option<usize>,
+}
+
+impl Randomizer for Nes {
+    fn next_piece(&mut self) -> &'static Piece {
+        let mut idx = self.rng.gen_range(0..=Piece::ALL.len());
+        if idx == Piece::ALL.len() || Some(idx) == self.last {
+            idx = self.rng.gen_range(0..Piece::ALL.len());
+        }
+        self.last = Some(idx);
+        Piece::ALL[idx]
+    }
+}
+
+// TGM style, remembers the last 4 pieces and rolls up to 4 times for one that isn't among them
+// The first piece is never an S, Z or O
+pub struct Tgm {
+    rng: ChaCha8Rng,
+    history: VecDeque<&'static Piece>,
+    first: bool,
+}
+
+impl Tgm {
+    const ROLLS: usize = 4;
+
+    pub fn new(rng: ChaCha8Rng) -> Self {
+        Tgm {
+            rng,
+            history: VecDeque::from(vec![&Piece::Z; 4]),
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for Tgm {
+    fn next_piece(&mut self) -> &'static Piece {
+        let piece = if self.first {
+            self.first = false;
+            *[&Piece::I, &Piece::T, &Piece::L, &Piece::J]
+                .choose(&mut self.rng)
+                .unwrap()
+        } else {
+            let mut piece = *Piece::ALL.choose(&mut self.rng).unwrap();
+            for _ in 1..Self::ROLLS {
+                if !self.history.contains(&piece) {
+                    break;
+                }
+                piece = *Piece::ALL.choose(&mut self.rng).unwrap();
+            }
+            piece
+        };
+
+        self.history.pop_front();
+        self.history.push_back(piece);
+        piece
+    }
+}
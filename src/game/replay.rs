@@ -0,0 +1,452 @@
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::{
+    clock::ManualClock,
+    config::GameConfig,
+    handling::{Handling, HeldInput},
+    level::Level,
+    lock::LockReset,
+    mode::{GameMode, Outcome},
+    randomizer::RandomizerKind,
+    rotation::RotationKind,
+    score::SpinRule,
+    Game,
+};
+
+// One call made to a game from outside, feeding the same calls at the same times to a game with the same
+// seed and rules plays it out exactly the same way
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Input {
+    // A call to update, made every tick and before every other input
+    Tick,
+    Press(HeldInput),
+    Release(HeldInput),
+    RotateLeft,
+    RotateRight,
+    Rotate180,
+    HardDrop,
+    Hold,
+    Pause,
+    Resume,
+}
+
+impl Input {
+    // Every input in the order of its byte in the file, so new ones have to go at the end
+    fn iterator() -> std::slice::Iter<'static, Input> {
+        static INPUTS: [Input; 14] = [
+            Input::Tick,
+            Input::Press(HeldInput::Left),
+            Input::Press(HeldInput::Right),
+            Input::Press(HeldInput::SoftDrop),
+            Input::Release(HeldInput::Left),
+            Input::Release(HeldInput::Right),
+            Input::Release(HeldInput::SoftDrop),
+            Input::RotateLeft,
+            Input::RotateRight,
+            Input::Rotate180,
+            Input::HardDrop,
+            Input::Hold,
+            Input::Pause,
+            Input::Resume,
+        ];
+        INPUTS.iter()
+    }
+}
+
+// How a game stood when its replay was taken, or when one was played back
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub outcome: Option<Outcome>,
+    pub score: u32,
+    pub lines: u32,
+    pub pieces: u32,
+    pub time: Duration,
+}
+
+// Everything needed to play a game back, along with how it ended up so a playback can be checked against it
+#[derive(Debug, Clone)]
+pub struct Replay {
+    // The seed is always filled in
+    pub config: GameConfig,
+    pub summary: Summary,
+    // Inputs with when they happened, measured on the game's clock from the moment it started
+    pub inputs: Vec<(Duration, Input)>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    NotAReplay,
+    UnsupportedVersion(u8),
+    Corrupt,
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{err}"),
+            ReplayError::NotAReplay => write!(f, "not a termtris replay"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "replay version {version} isn't supported")
+            }
+            ReplayError::Corrupt => write!(f, "replay is corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            // Running out of bytes halfway through means the file was cut short
+            io::ErrorKind::UnexpectedEof => ReplayError::Corrupt,
+            _ => ReplayError::Io(err),
+        }
+    }
+}
+
+// File layout, numbers marked varint are unsigned LEB128 and times are in nanoseconds
+//
+// "TTRP" version:u8
+// seed:u64 (little endian)
+// mode:u8 (0 marathon, 1 sprint, 2 ultra) then lines:varint for sprint or duration:varint for ultra
+// level:u8, only from version 2 on, older replays all started at level 1
+// randomizer:u8 rotation:u8 lock_reset:u8 spin_rule:u8, each the position in the kind's iterator
+// das:varint arr:varint sdf:varint
+// outcome:u8 (0 still going, then each Outcome in order) score:varint lines:varint pieces:varint time:varint
+// count:varint then count times input:u8 since_last:varint
+impl Replay {
+    const MAGIC: &'static [u8; 4] = b"TTRP";
+    pub const VERSION: u8 = 2;
+    pub const EXTENSION: &'static str = "ttr";
+
+    // Play every input back on a fresh game as fast as possible, without drawing anything
+    pub fn play(&self) -> Game {
+        let clock = ManualClock::new();
+        let mut game = Game::with_config(self.config.clone(), Box::new(clock.clone()));
+        for &(at, input) in &self.inputs {
+            clock.set(at);
+            game.apply(input);
+        }
+        game
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let bytes = fs::read(path).map_err(ReplayError::Io)?;
+        Self::read(&mut bytes.as_slice())
+    }
+
+    // Write the replay into the given directory, creating it if needed, returns the path of the new file
+    pub fn save(&self, dir: &Path, name: &str) -> io::Result<PathBuf> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes)?;
+        fs::create_dir_all(dir)?;
+        let path = dir.join(name).with_extension(Self::EXTENSION);
+        fs::write(&path, bytes)?;
+        Ok(path)
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(Self::MAGIC)?;
+        w.write_all(&[Self::VERSION])?;
+        w.write_all(&self.config.seed.unwrap_or(0).to_le_bytes())?;
+
+        match self.config.mode {
+            GameMode::Marathon => w.write_all(&[0])?,
+            GameMode::Sprint { lines } => {
+                w.write_all(&[1])?;
+                write_varint(w, lines as u64)?;
+            }
+            GameMode::Ultra { duration } => {
+                w.write_all(&[2])?;
+                write_varint(w, duration.as_nanos() as u64)?;
+            }
+        }
+        w.write_all(&[self.config.level])?;
+        w.write_all(&[
+            position(RandomizerKind::iterator(), &self.config.randomizer),
+            position(RotationKind::iterator(), &self.config.rotation),
+            position(LockReset::iterator(), &self.config.lock_reset),
+            position(SpinRule::iterator(), &self.config.spin_rule),
+        ])?;
+        write_varint(w, self.config.handling.das.as_nanos() as u64)?;
+        write_varint(w, self.config.handling.arr.as_nanos() as u64)?;
+        write_varint(w, self.config.handling.sdf as u64)?;
+
+        let outcome = match self.summary.outcome {
+            None => 0,
+            Some(Outcome::GoalReached) => 1,
+            Some(Outcome::TimeUp) => 2,
+            Some(Outcome::BlockOut) => 3,
+            Some(Outcome::LockOut) => 4,
+        };
+        w.write_all(&[outcome])?;
+        write_varint(w, self.summary.score as u64)?;
+        write_varint(w, self.summary.lines as u64)?;
+        write_varint(w, self.summary.pieces as u64)?;
+        write_varint(w, self.summary.time.as_nanos() as u64)?;
+
+        write_varint(w, self.inputs.len() as u64)?;
+        let mut last = Duration::ZERO;
+        for &(at, input) in &self.inputs {
+            w.write_all(&[position(Input::iterator(), &input)])?;
+            write_varint(w, (at - last).as_nanos() as u64)?;
+            last = at;
+        }
+        Ok(())
+    }
+
+    pub fn read(r: &mut impl Read) -> Result<Self, ReplayError> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)
+            .map_err(|_| ReplayError::NotAReplay)?;
+        if &magic != Self::MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+        let version = read_u8(r)?;
+        if !(1..=Self::VERSION).contains(&version) {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let mut seed = [0; 8];
+        r.read_exact(&mut seed)?;
+        let mode = match read_u8(r)? {
+            0 => GameMode::Marathon,
+            1 => GameMode::Sprint {
+                lines: read_varint(r)? as u32,
+            },
+            2 => GameMode::Ultra {
+                duration: Duration::from_nanos(read_varint(r)?),
+            },
+            _ => return Err(ReplayError::Corrupt),
+        };
+        let level = if version >= 2 { read_u8(r)? } else { 1 };
+        if !(1..=Level::MAX).contains(&level) {
+            return Err(ReplayError::Corrupt);
+        }
+        let config = GameConfig {
+            seed: Some(u64::from_le_bytes(seed)),
+            mode,
+            level,
+            randomizer: nth(RandomizerKind::iterator(), read_u8(r)?)?,
+            rotation: nth(RotationKind::iterator(), read_u8(r)?)?,
+            lock_reset: nth(LockReset::iterator(), read_u8(r)?)?,
+            spin_rule: nth(SpinRule::iterator(), read_u8(r)?)?,
+            handling: Handling {
+                das: Duration::from_nanos(read_varint(r)?),
+                arr: Duration::from_nanos(read_varint(r)?),
+                sdf: u32::try_from(read_varint(r)?).map_err(|_| ReplayError::Corrupt)?,
+            },
+        };
+        // Handling the game could never have been played with, and that could keep a playback busy forever
+        if !config.handling.is_valid() {
+            return Err(ReplayError::Corrupt);
+        }
+
+        let outcome = match read_u8(r)? {
+            0 => None,
+            1 => Some(Outcome::GoalReached),
+            2 => Some(Outcome::TimeUp),
+            3 => Some(Outcome::BlockOut),
+            4 => Some(Outcome::LockOut),
+            _ => return Err(ReplayError::Corrupt),
+        };
+        let summary = Summary {
+            outcome,
+            score: read_varint(r)? as u32,
+            lines: read_varint(r)? as u32,
+            pieces: read_varint(r)? as u32,
+            time: Duration::from_nanos(read_varint(r)?),
+        };
+
+        let count = read_varint(r)?;
+        let mut inputs = Vec::new();
+        let mut at = Duration::ZERO;
+        for _ in 0..count {
+            let input = nth(Input::iterator(), read_u8(r)?)?;
+            at += Duration::from_nanos(read_varint(r)?);
+            inputs.push((at, input));
+        }
+
+        Ok(Replay {
+            config,
+            summary,
+            inputs,
+        })
+    }
+}
+
+fn position<T: PartialEq + 'static>(mut iter: std::slice::Iter<'static, T>, item: &T) -> u8 {
+    // Every iterator covers all of its type's values
+    iter.position(|other| other == item).unwrap() as u8
+}
+
+fn nth<T: Copy + 'static>(mut iter: std::slice::Iter<'static, T>, n: u8) -> Result<T, ReplayError> {
+    iter.nth(n as usize).copied().ok_or(ReplayError::Corrupt)
+}
+
+fn read_u8(r: &mut impl Read) -> Result<u8, ReplayError> {
+    let mut byte = [0];
+    r.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn write_varint(w: &mut impl Write, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint(r: &mut impl Read) -> Result<u64, ReplayError> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = read_u8(r)?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ReplayError::Corrupt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A short game played on a manual clock, dropping pieces with a few moves and spins in between
+    fn replay() -> Replay {
+        let clock = ManualClock::new();
+        let config = GameConfig {
+            seed: Some(42),
+            level: 3,
+            ..GameConfig::default()
+        };
+        let mut game = Game::with_config(config, Box::new(clock.clone()));
+        let inputs = [
+            Input::Press(HeldInput::Left),
+            Input::RotateRight,
+            Input::Release(HeldInput::Left),
+            Input::HardDrop,
+            Input::Press(HeldInput::Right),
+            Input::Rotate180,
+            Input::Release(HeldInput::Right),
+            Input::Hold,
+            Input::HardDrop,
+        ];
+        for _ in 0..10 {
+            for &input in &inputs {
+                clock.advance(Duration::from_millis(137));
+                game.update();
+                game.apply(input);
+            }
+        }
+        game.take_replay().unwrap()
+    }
+
+    fn bytes(replay: &Replay) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        replay.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = replay();
+        let written = bytes(&replay);
+        let read = Replay::read(&mut written.as_slice()).unwrap();
+        assert_eq!(read.summary, replay.summary);
+        assert_eq!(read.inputs, replay.inputs);
+        assert_eq!(read.config.level, 3);
+        assert_eq!(bytes(&read), written);
+    }
+
+    #[test]
+    fn playback_matches_the_recording() {
+        let replay = replay();
+        assert!(replay.summary.pieces > 0);
+        assert_eq!(replay.play().summary(), replay.summary);
+    }
+
+    #[test]
+    fn version_1_starts_at_level_1() {
+        let mut bytes = bytes(&replay());
+        bytes[4] = 1;
+        // The level byte comes straight after the marathon mode byte
+        bytes.remove(4 + 1 + 8 + 1);
+        let read = Replay::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.config.level, 1);
+    }
+
+    #[test]
+    fn truncated_replay_is_corrupt() {
+        let bytes = bytes(&replay());
+        for len in [5, 20, bytes.len() - 1] {
+            assert!(matches!(
+                Replay::read(&mut &bytes[..len]),
+                Err(ReplayError::Corrupt)
+            ));
+        }
+    }
+
+    #[test]
+    fn impossible_handling_is_corrupt() {
+        let replay = replay();
+        for handling in [
+            Handling {
+                sdf: Handling::MAX_SDF + 1,
+                ..replay.config.handling
+            },
+            Handling {
+                arr: Duration::from_nanos(1),
+                ..replay.config.handling
+            },
+        ] {
+            let replay = Replay {
+                config: GameConfig {
+                    handling,
+                    ..replay.config.clone()
+                },
+                ..replay.clone()
+            };
+            assert!(matches!(
+                Replay::read(&mut bytes(&replay).as_slice()),
+                Err(ReplayError::Corrupt)
+            ));
+        }
+    }
+
+    #[test]
+    fn unknown_version_is_unsupported() {
+        let mut bytes = bytes(&replay());
+        bytes[4] = Replay::VERSION + 1;
+        assert!(matches!(
+            Replay::read(&mut bytes.as_slice()),
+            Err(ReplayError::UnsupportedVersion(version)) if version == Replay::VERSION + 1
+        ));
+        assert!(matches!(
+            Replay::read(&mut &b"TTRQ"[..]),
+            Err(ReplayError::NotAReplay)
+        ));
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value).unwrap();
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), value);
+        }
+    }
+}
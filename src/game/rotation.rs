@@ -0,0 +1,273 @@
+use std::fmt::{self, Display};
+
+use super::piece::{Piece, PieceKind};
+
+type Shapes = [[[u8; 4]; 4]; 4];
+
+// How pieces turn, what they look like at each rotation and where they go when a turn is blocked
+// Rotation 0 is always the orientation pieces spawn in
+pub trait RotationSystem {
+    fn shape(&self, piece: &'static Piece, rotation: u8) -> &'static [[u8; 4]; 4];
+    // Offsets to try in order when the piece can't turn in place, blocked is the first cell of the new shape
+    // that overlaps something, counting along each row from the top left
+    fn kicks(
+        &self,
+        piece: &'static Piece,
+        from: u8,
+        to: u8,
+        blocked: (usize, usize),
+    ) -> &'static [(isize, isize)];
+    // Row and column of the top left corner of a piece's shape when it spawns
+    fn spawn_offset(&self) -> (isize, isize);
+}
+
+// The available rotation systems, used to pick one for a game
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum RotationKind {
+    #[default]
+    Srs,
+    Ars,
+    Nes,
+    Sega,
+}
+
+impl RotationKind {
+    // Iterator over all available rotation systems
+    pub fn iterator() -> std::slice::Iter<'static, RotationKind> {
+        static KINDS: [RotationKind; 4] = [
+            RotationKind::Srs,
+            RotationKind::Ars,
+            RotationKind::Nes,
+            RotationKind::Sega,
+        ];
+        KINDS.iter()
+    }
+
+    pub fn system(&self) -> &'static dyn RotationSystem {
+        match self {
+            RotationKind::Srs => &Srs,
+            RotationKind::Ars => &Ars,
+            RotationKind::Nes => &Nes,
+            RotationKind::Sega => &Sega,
+        }
+    }
+}
+
+impl Display for RotationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RotationKind::Srs => write!(f, "SRS"),
+            RotationKind::Ars => write!(f, "ARS"),
+            RotationKind::Nes => write!(f, "NES"),
+            RotationKind::Sega => write!(f, "Sega"),
+        }
+    }
+}
+
+// The guideline Super Rotation System, its shapes and kicks are stored with the pieces
+struct Srs;
+
+impl RotationSystem for Srs {
+    fn shape(&self, piece: &'static Piece, rotation: u8) -> &'static [[u8; 4]; 4] {
+        &piece.shapes[rotation as usize]
+    }
+
+    fn kicks(
+        &self,
+        piece: &'static Piece,
+        from: u8,
+        to: u8,
+        _blocked: (usize, usize),
+    ) -> &'static [(isize, isize)] {
+        if (to + 4 - from) % 4 == 2 {
+            &piece.kicks_180[from as usize]
+        } else {
+            &piece.kicks[from as usize][to as usize]
+        }
+    }
+
+    fn spawn_offset(&self) -> (isize, isize) {
+        (21, 3)
+    }
+}
+
+// Arika Rotation System from the TGM games, Sega's rotation with a kick one column right and then left
+// I never kicks, and L, J and T don't kick when the first blocked cell is in the centre column
+struct Ars;
+
+impl Ars {
+    const KICKS: [(isize, isize); 2] = [(1, 0), (-1, 0)];
+}
+
+impl RotationSystem for Ars {
+    fn shape(&self, piece: &'static Piece, rotation: u8) -> &'static [[u8; 4]; 4] {
+        &sega_shapes(piece.kind)[rotation as usize]
+    }
+
+    fn kicks(
+        &self,
+        piece: &'static Piece,
+        _from: u8,
+        _to: u8,
+        blocked: (usize, usize),
+    ) -> &'static [(isize, isize)] {
+        match piece.kind {
+            PieceKind::I => &[],
+            PieceKind::L | PieceKind::J | PieceKind::T if blocked.1 == 1 => &[],
+            _ => &Self::KICKS,
+        }
+    }
+
+    fn spawn_offset(&self) -> (isize, isize) {
+        (22, 3)
+    }
+}
+
+// Nintendo rotation from NES Tetris, pieces turn about their centre with no kicks
+// S, Z and I only have two orientations and sit right of centre when upright
+struct Nes;
+
+impl RotationSystem for Nes {
+    fn shape(&self, piece: &'static Piece, rotation: u8) -> &'static [[u8; 4]; 4] {
+        let shapes = match piece.kind {
+            PieceKind::O => &O_SHAPES,
+            PieceKind::I => &NES_I_SHAPES,
+            PieceKind::S => &NES_S_SHAPES,
+            PieceKind::Z => &Z_SHAPES,
+            PieceKind::T => &NES_T_SHAPES,
+            PieceKind::L => &NES_L_SHAPES,
+            PieceKind::J => &NES_J_SHAPES,
+        };
+        &shapes[rotation as usize]
+    }
+
+    fn kicks(
+        &self,
+        _piece: &'static Piece,
+        _from: u8,
+        _to: u8,
+        _blocked: (usize, usize),
+    ) -> &'static [(isize, isize)] {
+        &[]
+    }
+
+    fn spawn_offset(&self) -> (isize, isize) {
+        (22, 3)
+    }
+}
+
+// Classic Sega rotation, pieces spawn flat side up and rest on the bottom of their box, with no kicks
+struct Sega;
+
+impl RotationSystem for Sega {
+    fn shape(&self, piece: &'static Piece, rotation: u8) -> &'static [[u8; 4]; 4] {
+        &sega_shapes(piece.kind)[rotation as usize]
+    }
+
+    fn kicks(
+        &self,
+        _piece: &'static Piece,
+        _from: u8,
+        _to: u8,
+        _blocked: (usize, usize),
+    ) -> &'static [(isize, isize)] {
+        &[]
+    }
+
+    fn spawn_offset(&self) -> (isize, isize) {
+        (22, 3)
+    }
+}
+
+fn sega_shapes(kind: PieceKind) -> &'static Shapes {
+    match kind {
+        PieceKind::O => &O_SHAPES,
+        PieceKind::I => &SEGA_I_SHAPES,
+        PieceKind::S => &SEGA_S_SHAPES,
+        PieceKind::Z => &Z_SHAPES,
+        PieceKind::T => &SEGA_T_SHAPES,
+        PieceKind::L => &SEGA_L_SHAPES,
+        PieceKind::J => &SEGA_J_SHAPES,
+    }
+}
+
+// Shape data from here https://tetris.wiki/Nintendo_Rotation_System and https://tetris.wiki/Sega_Rotation
+const O_SHAPES: Shapes = [[[0, 0, 0, 0], [0, 1, 1, 0], [0, 1, 1, 0], [0, 0, 0, 0]]; 4];
+
+// Z is the same in both, upright it sits right of centre
+const Z_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [1, 1, 0, 0], [0, 1, 1, 0], [0, 0, 0, 0]],
+    [[0, 0, 1, 0], [0, 1, 1, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 0, 0], [1, 1, 0, 0], [0, 1, 1, 0], [0, 0, 0, 0]],
+    [[0, 0, 1, 0], [0, 1, 1, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+];
+
+const NES_I_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [0, 0, 0, 0], [1, 1, 1, 1], [0, 0, 0, 0]],
+    [[0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0]],
+    [[0, 0, 0, 0], [0, 0, 0, 0], [1, 1, 1, 1], [0, 0, 0, 0]],
+    [[0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0]],
+];
+
+const NES_S_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [0, 1, 1, 0], [1, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 1, 1, 0], [0, 0, 1, 0], [0, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 1, 1, 0], [1, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 1, 1, 0], [0, 0, 1, 0], [0, 0, 0, 0]],
+];
+
+const NES_T_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [1, 1, 1, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [1, 1, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [1, 1, 1, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 1, 1, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+];
+
+const NES_L_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [1, 1, 1, 0], [1, 0, 0, 0], [0, 0, 0, 0]],
+    [[1, 1, 0, 0], [0, 1, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 1, 0], [1, 1, 1, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 1, 0, 0], [0, 1, 1, 0], [0, 0, 0, 0]],
+];
+
+const NES_J_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [1, 1, 1, 0], [0, 0, 1, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 1, 0, 0], [1, 1, 0, 0], [0, 0, 0, 0]],
+    [[1, 0, 0, 0], [1, 1, 1, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
+    [[0, 1, 1, 0], [0, 1, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+];
+
+const SEGA_I_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [1, 1, 1, 1], [0, 0, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0]],
+    [[0, 0, 0, 0], [1, 1, 1, 1], [0, 0, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0], [0, 0, 1, 0]],
+];
+
+const SEGA_S_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [0, 1, 1, 0], [1, 1, 0, 0], [0, 0, 0, 0]],
+    [[1, 0, 0, 0], [1, 1, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 1, 1, 0], [1, 1, 0, 0], [0, 0, 0, 0]],
+    [[1, 0, 0, 0], [1, 1, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+];
+
+const SEGA_T_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [1, 1, 1, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [1, 1, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 1, 0, 0], [1, 1, 1, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 1, 1, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+];
+
+const SEGA_L_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [1, 1, 1, 0], [1, 0, 0, 0], [0, 0, 0, 0]],
+    [[1, 1, 0, 0], [0, 1, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 0, 0], [0, 0, 1, 0], [1, 1, 1, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 1, 0, 0], [0, 1, 1, 0], [0, 0, 0, 0]],
+];
+
+const SEGA_J_SHAPES: Shapes = [
+    [[0, 0, 0, 0], [1, 1, 1, 0], [0, 0, 1, 0], [0, 0, 0, 0]],
+    [[0, 1, 0, 0], [0, 1, 0, 0], [1, 1, 0, 0], [0, 0, 0, 0]],
+    [[0, 0, 0, 0], [1, 0, 0, 0], [1, 1, 1, 0], [0, 0, 0, 0]],
+    [[0, 1, 1, 0], [0, 1, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+];
@@ -1,7 +1,7 @@
+use std::fmt::{self, Display};
+
 use self::ScoreEvent::*;
-use super::level::Level;
-use microkv::MicroKV;
-use tui::style::Color;
+use super::{level::Level, piece::PieceKind};
 
 // Enum with a variant for every scoring move, and an EndTurn to signal when score should be calculated and updated
 // It might make more sense to split off end turn into a separate function but I'm too lazy at the moment
@@ -9,8 +9,12 @@ use tui::style::Color;
 pub enum ScoreEvent {
     LineClear(Lines),
     TSpin(TSpins),
+    // A spin with any other piece, scored the same as a T-spin of the given kind
+    Spin(PieceKind, TSpins),
     SoftDrop(isize),
     HardDrop(isize),
+    // The turn's clear left the board empty
+    PerfectClear,
     EndTurn,
 }
 
@@ -23,6 +27,18 @@ pub enum Lines {
     Tetris,
 }
 
+impl Lines {
+    pub fn count(&self) -> u32 {
+        match self {
+            Lines::None => 0,
+            Lines::Single => 1,
+            Lines::Double => 2,
+            Lines::Triple => 3,
+            Lines::Tetris => 4,
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Clone, Copy)]
 pub enum TSpins {
     None,
@@ -30,42 +46,83 @@ pub enum TSpins {
     TSpin,
 }
 
+// Which pieces can score spins, and how spins with pieces other than T are scored
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SpinRule {
+    // Guideline, only T-spins count
+    #[default]
+    TOnly,
+    // Other pieces stuck in place by a rotation score as mini spins
+    AllMini,
+    // Other pieces stuck in place by a rotation score as full spins
+    AllSpin,
+}
+
+impl SpinRule {
+    // Iterator over all spin rules
+    pub fn iterator() -> std::slice::Iter<'static, SpinRule> {
+        static RULES: [SpinRule; 3] = [SpinRule::TOnly, SpinRule::AllMini, SpinRule::AllSpin];
+        RULES.iter()
+    }
+}
+
+impl Display for SpinRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpinRule::TOnly => write!(f, "T Only"),
+            SpinRule::AllMini => write!(f, "All-Mini"),
+            SpinRule::AllSpin => write!(f, "All-Spin"),
+        }
+    }
+}
+
+// How a turn should stand out in the score log, left to the renderer to turn into a color
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Highlight {
+    Normal,
+    Combo,
+    BackToBack,
+    PerfectClear,
+}
+
 // Struct to store data about the current score with methods to interact with it
 pub struct Score {
     pub score: u32,
     pub level: Level,
-    high_score: u32,
-    high_score_db: MicroKV,
+    cleared_lines: u32,
     turn: (TSpins, Lines),
+    // Piece the turn's spin was done with, only used to name it in the log
+    spin_piece: PieceKind,
     turn_score: u32,
-    last_turn: (TSpins, Lines),
     last_turn_score: u32,
     last_turn_text: String,
-    text_color: Color,
+    highlight: Highlight,
+    // Consecutive line clearing turns after the first, None once a turn doesn't clear anything
+    combo: Option<u32>,
+    // Difficult clears in a row after the first, None once an easier clear breaks the chain
+    b2b: Option<u32>,
+    turn_perfect_clear: bool,
+    last_turn_perfect_clear: bool,
+    perfect_clears: u32,
 }
 
-// I can't think of a reason to have a Default impl for any structs in this project but I'm open to any reasons
-#[allow(clippy::new_without_default)]
-impl<'a> Score {
-    pub fn new() -> Self {
-        let high_score_db = MicroKV::open("score.data")
-            .expect("Failed to create MicroKV")
-            .set_auto_commit(true);
-        let high_score = match high_score_db.get("score") {
-            Ok(Some(score)) => score,
-            _ => 0,
-        };
+impl Score {
+    pub fn new(level: Level) -> Self {
         Score {
             score: 0,
-            level: Level::new(),
-            high_score,
-            high_score_db,
+            level,
+            cleared_lines: 0,
             turn_score: 0,
             last_turn_score: 0,
             last_turn_text: String::new(),
             turn: (TSpins::None, Lines::None),
-            last_turn: (TSpins::None, Lines::None),
-            text_color: Color::Gray,
+            spin_piece: PieceKind::T,
+            highlight: Highlight::Normal,
+            combo: None,
+            b2b: None,
+            turn_perfect_clear: false,
+            last_turn_perfect_clear: false,
+            perfect_clears: 0,
         }
     }
 
@@ -77,22 +134,41 @@ impl<'a> Score {
         &self.last_turn_text
     }
 
-    pub fn text_color(&self) -> Color {
-        self.text_color
+    pub fn highlight(&self) -> Highlight {
+        self.highlight
     }
 
-    pub fn score(&self) -> u32 {
-        self.score
+    // Current REN count, 0 straight after the first clear of a chain
+    pub fn combo(&self) -> Option<u32> {
+        self.combo
+    }
+
+    // Current back to back streak, 0 straight after the difficult clear that started it
+    pub fn back_to_back(&self) -> Option<u32> {
+        self.b2b
+    }
+
+    pub fn last_turn_perfect_clear(&self) -> bool {
+        self.last_turn_perfect_clear
+    }
+
+    pub fn perfect_clears(&self) -> u32 {
+        self.perfect_clears
     }
 
-    pub fn high_score(&self) -> u32 {
-        self.high_score
+    pub fn score(&self) -> u32 {
+        self.score
     }
 
     pub fn lines(&self) -> u32 {
         self.level.lines
     }
 
+    // Number of lines actually cleared, unlike lines() which counts towards the level goal
+    pub fn cleared_lines(&self) -> u32 {
+        self.cleared_lines
+    }
+
     pub fn lines_goal(&self) -> u32 {
         self.level.lines_goal
     }
@@ -105,131 +181,110 @@ impl<'a> Score {
         self.level.add_lines(lines)
     }
 
-    pub fn save_and_reset_score(&mut self) {
-        if self.score > self.high_score {
-            self.high_score = self.score;
-            self.high_score_db.put("score", &self.high_score).unwrap();
-        }
-
-        self.score = 0;
-        self.level = Level::new();
-    }
-
     pub fn do_event(&mut self, event: ScoreEvent) {
         match event {
             LineClear(lines) => self.turn.1 = lines,
             TSpin(spin) => self.turn.0 = spin,
+            Spin(piece, spin) => {
+                self.turn.0 = spin;
+                self.spin_piece = piece;
+            }
             SoftDrop(len) => self.turn_score += len as u32,
             HardDrop(len) => self.turn_score += 2 * len as u32,
+            PerfectClear => self.turn_perfect_clear = true,
             EndTurn => {
                 // Do score calculations
 
-                self.last_turn_text = String::new();
+                // There's no such thing as a mini spin triple, one is just a triple and doesn't count as a spin
+                if self.turn == (TSpins::MiniTSpin, Lines::Triple) {
+                    self.turn.0 = TSpins::None;
+                }
 
-                // Combo points
-                if self.last_turn != (TSpins::None, Lines::None)
-                    && self.turn != (TSpins::None, Lines::None)
-                {
-                    self.turn_score += 50 * self.level() as u32;
-                    self.text_color = Color::Cyan;
-                } else {
-                    self.text_color = Color::Gray;
+                // Combo points, every clear in a row after the first is worth 50 per clear so far
+                self.combo = match (self.turn.1, self.combo) {
+                    (Lines::None, _) => None,
+                    (_, None) => Some(0),
+                    (_, Some(combo)) => Some(combo + 1),
+                };
+                match self.combo {
+                    Some(combo) if combo > 0 => {
+                        self.turn_score += 50 * combo * self.level() as u32;
+                        self.highlight = Highlight::Combo;
+                    }
+                    _ => self.highlight = Highlight::Normal,
                 }
 
                 // Score data taken from here: https://tetris.fandom.com/wiki/Scoring#Guideline_scoring_system
-                match self.turn {
-                    (TSpins::None, Lines::Single) => {
-                        // Single
-                        self.turn_score += 100 * self.level() as u32;
-                        self.last_turn_text = "Single".to_string();
-                    }
-                    (TSpins::MiniTSpin, Lines::None) => {
-                        // Mini T-Spin
-                        self.turn_score += 100 * self.level() as u32;
-                        self.last_turn_text = "Mini T-Spin".to_string();
-                    }
-                    (TSpins::MiniTSpin, Lines::Single) => {
-                        // Mini T-Spin Single
-                        self.turn_score += 200 * self.level() as u32;
-                        self.last_turn_text = "Mini T-Spin Single".to_string();
-                    }
-                    (TSpins::None, Lines::Double) => {
-                        // Double
-                        self.turn_score += 300 * self.level() as u32;
-                        self.last_turn_text = "Double".to_string();
-                    }
-                    (TSpins::MiniTSpin, Lines::Double) => {
-                        // Mini T-Spin Double
-                        if self.last_turn == (TSpins::MiniTSpin, Lines::Double) {
-                            // Back to back
-                            self.turn_score += 600 * self.level() as u32;
-                            self.last_turn_text = "B2B Mini T-Spin Double".to_owned();
-                            self.text_color = Color::LightMagenta;
-                        } else {
-                            // Once
-                            self.turn_score += 400 * self.level() as u32;
-                            self.last_turn_text = "Mini T-Spin Double".to_string();
-                        }
-                    }
-                    (TSpins::TSpin, Lines::None) => {
-                        // T-Spin
-                        self.turn_score += 400 * self.level() as u32;
-                        self.last_turn_text = "T-Spin".to_string();
-                    }
-                    (TSpins::None, Lines::Triple) | (TSpins::MiniTSpin, Lines::Triple) => {
-                        // Triple
-                        self.turn_score += 500 * self.level() as u32;
-                        self.last_turn_text = "Triple".to_string();
-                    }
-                    (_, Lines::Tetris) => {
-                        // Tetris
-                        if self.last_turn.1 == Lines::Tetris {
-                            self.turn_score += 1200 * self.level() as u32;
-                            self.last_turn_text = "B2B Tetris".to_string();
-                            self.text_color = Color::LightMagenta;
-                        } else {
-                            self.turn_score += 800 * self.level() as u32;
-                            self.last_turn_text = "Tetris".to_string();
-                        }
-                    }
-                    (TSpins::TSpin, Lines::Single) => {
-                        // T-Spin Single
-                        if self.last_turn == (TSpins::TSpin, Lines::Single) {
-                            self.turn_score += 1200 * self.level() as u32;
-                            self.last_turn_text = "B2B T-Spin Single".to_string();
-                            self.text_color = Color::LightMagenta;
-                        } else {
-                            self.turn_score += 800 * self.level() as u32;
-                            self.last_turn_text = "T-Spin Single".to_string();
-                        }
-                    }
-                    (TSpins::TSpin, Lines::Double) => {
-                        // T-Spin Double
-                        if self.last_turn == (TSpins::TSpin, Lines::Double) {
-                            self.turn_score += 1800 * self.level() as u32;
-                            self.last_turn_text = "B2B T-Spin Double".to_string();
-                            self.text_color = Color::LightMagenta;
-                        } else {
-                            self.turn_score += 1200 * self.level() as u32;
-                            self.last_turn_text = "T-Spin Double".to_string();
-                        }
-                    }
-                    (TSpins::TSpin, Lines::Triple) => {
-                        // T-Spin Triple
-                        if self.last_turn == (TSpins::TSpin, Lines::Triple) {
-                            self.turn_score += 2400 * self.level() as u32;
-                            self.last_turn_text = "B2B T-Spin Triple".to_string();
-                            self.text_color = Color::LightMagenta;
-                        } else {
-                            self.turn_score += 1600 * self.level() as u32;
-                            self.last_turn_text = "T-Spin Triple".to_string();
-                        }
+                let (points, text) = match self.turn {
+                    (TSpins::None, Lines::None) => (0, "-"),
+                    (TSpins::None, Lines::Single) => (100, "Single"),
+                    (TSpins::None, Lines::Double) => (300, "Double"),
+                    // Mini triples were turned into plain triples above
+                    (TSpins::TSpin, Lines::Triple) => (1600, "T-Spin Triple"),
+                    (_, Lines::Triple) => (500, "Triple"),
+                    (_, Lines::Tetris) => (800, "Tetris"),
+                    (TSpins::MiniTSpin, Lines::None) => (100, "Mini T-Spin"),
+                    (TSpins::MiniTSpin, Lines::Single) => (200, "Mini T-Spin Single"),
+                    (TSpins::MiniTSpin, Lines::Double) => (400, "Mini T-Spin Double"),
+                    (TSpins::TSpin, Lines::None) => (400, "T-Spin"),
+                    (TSpins::TSpin, Lines::Single) => (800, "T-Spin Single"),
+                    (TSpins::TSpin, Lines::Double) => (1200, "T-Spin Double"),
+                };
+
+                // Tetrises and spins that clear lines are difficult and keep the back to back chain going,
+                // any other clear breaks it and turns without a clear leave it alone
+                let difficult = match self.turn {
+                    (_, Lines::None) => false,
+                    (spin, lines) => lines == Lines::Tetris || spin != TSpins::None,
+                };
+                if self.turn.1 != Lines::None {
+                    self.b2b = match (difficult, self.b2b) {
+                        (false, _) => None,
+                        (true, None) => Some(0),
+                        (true, Some(streak)) => Some(streak + 1),
+                    };
+                }
+
+                let back_to_back = difficult && matches!(self.b2b, Some(streak) if streak > 0);
+                match self.b2b {
+                    Some(streak) if back_to_back => {
+                        // Back to back, worth half as much again
+                        self.turn_score += points * 3 / 2 * self.level() as u32;
+                        self.last_turn_text = format!("B2B x{streak} {text}");
+                        self.highlight = Highlight::BackToBack;
                     }
-                    (TSpins::None, Lines::None) => {
-                        self.last_turn_text = "-".to_string();
+                    _ => {
+                        self.turn_score += points * self.level() as u32;
+                        self.last_turn_text = text.to_string();
                     }
                 }
 
+                // Perfect clears are worth a bonus on top of the clear itself, more for a back to back tetris
+                if self.turn_perfect_clear {
+                    let bonus = match self.turn.1 {
+                        Lines::None => 0,
+                        Lines::Single => 800,
+                        Lines::Double => 1200,
+                        Lines::Triple => 1800,
+                        Lines::Tetris if back_to_back => 3200,
+                        Lines::Tetris => 2000,
+                    };
+                    self.turn_score += bonus * self.level() as u32;
+                    self.perfect_clears += 1;
+                }
+
+                if self.spin_piece != PieceKind::T {
+                    self.last_turn_text = self
+                        .last_turn_text
+                        .replace("T-Spin", &format!("{}-Spin", self.spin_piece));
+                }
+
+                if let Some(combo) = self.combo.filter(|combo| *combo > 0) {
+                    self.last_turn_text = format!("{} {combo} REN", self.last_turn_text);
+                }
+
+                self.cleared_lines += self.turn.1.count();
+
                 // Count lines (Numbers from here https://tetris.fandom.com/wiki/Tetris_Guideline)
                 match self.turn.1 {
                     Lines::Single => self.add_lines(1),
@@ -239,8 +294,10 @@ impl<'a> Score {
                     Lines::None => {}
                 }
 
-                self.last_turn = self.turn;
                 self.turn = (TSpins::None, Lines::None);
+                self.spin_piece = PieceKind::T;
+                self.last_turn_perfect_clear = self.turn_perfect_clear;
+                self.turn_perfect_clear = false;
 
                 self.score += self.turn_score;
                 self.last_turn_score = self.turn_score;
@@ -249,3 +306,92 @@ impl<'a> Score {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Low enough that none of the clears in these tests reach the next level
+    const LEVEL: u32 = 5;
+
+    // Play out a turn that clears the given lines with the given spin, returns the points it scored
+    fn turn(score: &mut Score, spin: Option<ScoreEvent>, lines: Lines) -> u32 {
+        if let Some(spin) = spin {
+            score.do_event(spin);
+        }
+        score.do_event(LineClear(lines));
+        score.do_event(EndTurn);
+        score.last_turn_score()
+    }
+
+    // A back to back chain started by a tetris, followed by a turn without a clear so there's no combo going
+    fn chain_started() -> Score {
+        let mut score = Score::new(Level::new(LEVEL as u8));
+        turn(&mut score, None, Lines::Tetris);
+        turn(&mut score, None, Lines::None);
+        score
+    }
+
+    #[test]
+    fn mini_spin_triple_is_a_plain_triple() {
+        let mut score = chain_started();
+        let mini = Some(Spin(PieceKind::L, TSpins::MiniTSpin));
+        assert_eq!(turn(&mut score, mini, Lines::Triple), 500 * LEVEL);
+        assert_eq!(score.last_turn_text(), "Triple");
+        assert_eq!(score.back_to_back(), None);
+    }
+
+    #[test]
+    fn tspin_double_after_tetris_is_back_to_back() {
+        let mut score = chain_started();
+        let points = turn(&mut score, Some(TSpin(TSpins::TSpin)), Lines::Double);
+        assert_eq!(points, 1200 * 3 / 2 * LEVEL);
+        assert_eq!(score.last_turn_text(), "B2B x1 T-Spin Double");
+        assert_eq!(score.highlight(), Highlight::BackToBack);
+    }
+
+    #[test]
+    fn single_breaks_back_to_back() {
+        let mut score = chain_started();
+        turn(&mut score, None, Lines::Single);
+        assert_eq!(score.back_to_back(), None);
+        turn(&mut score, None, Lines::None);
+        assert_eq!(turn(&mut score, None, Lines::Tetris), 800 * LEVEL);
+        assert_eq!(score.back_to_back(), Some(0));
+    }
+
+    #[test]
+    fn turn_without_a_clear_keeps_back_to_back() {
+        let mut score = chain_started();
+        turn(&mut score, Some(TSpin(TSpins::TSpin)), Lines::None);
+        assert_eq!(score.back_to_back(), Some(0));
+        turn(&mut score, None, Lines::Tetris);
+        assert_eq!(score.back_to_back(), Some(1));
+    }
+
+    #[test]
+    fn back_to_back_tetris_perfect_clear_bonus() {
+        let mut score = chain_started();
+        score.do_event(PerfectClear);
+        let points = turn(&mut score, None, Lines::Tetris);
+        assert_eq!(points, (800 * 3 / 2 + 3200) * LEVEL);
+        assert!(score.last_turn_perfect_clear());
+        assert_eq!(score.perfect_clears(), 1);
+    }
+
+    #[test]
+    fn ren_adds_fifty_per_clear_in_a_row() {
+        let mut score = Score::new(Level::new(LEVEL as u8));
+        assert_eq!(turn(&mut score, None, Lines::Single), 100 * LEVEL);
+        assert_eq!(score.last_turn_text(), "Single");
+        assert_eq!(turn(&mut score, None, Lines::Single), (100 + 50) * LEVEL);
+        assert_eq!(score.last_turn_text(), "Single 1 REN");
+        assert_eq!(turn(&mut score, None, Lines::Double), (300 + 100) * LEVEL);
+        assert_eq!(score.last_turn_text(), "Double 2 REN");
+        assert_eq!(score.highlight(), Highlight::Combo);
+
+        turn(&mut score, None, Lines::None);
+        assert_eq!(score.combo(), None);
+        assert_eq!(turn(&mut score, None, Lines::Single), 100 * LEVEL);
+    }
+}
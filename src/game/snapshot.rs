@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use super::{board::Board, mode::GameMode, piece::Piece, rotation::RotationKind, score::Highlight};
+
+// A single line of the score log
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub text: String,
+    pub highlight: Highlight,
+}
+
+// Plain copy of everything needed to display or inspect a game at one point in time
+// The board has the current piece and its ghost drawn in, use Game::board for the locked cells only
+#[derive(Clone)]
+pub struct Snapshot {
+    pub board: Board,
+    pub next_pieces: Vec<&'static Piece>,
+    pub hold_piece: Option<&'static Piece>,
+    pub score: u32,
+    pub last_turn_score: u32,
+    pub level: u8,
+    pub lines: u32,
+    pub lines_goal: u32,
+    pub cleared_lines: u32,
+    pub back_to_back: Option<u32>,
+    pub mode: GameMode,
+    pub elapsed: Duration,
+    pub score_log: Vec<LogEntry>,
+    pub seed: u64,
+    // Decides what the next and hold pieces look like
+    pub rotation: RotationKind,
+}
@@ -1,4 +1,7 @@
-use std::fmt::{self, Display};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
@@ -8,6 +11,7 @@ pub enum BaseKey {
     Char(char),
     Enter,
     Tab,
+    Esc,
     Left,
     Right,
     Up,
@@ -28,6 +32,7 @@ impl Display for BaseKey {
             BaseKey::Char(c) => write!(f, "{}", c),
             BaseKey::Enter => write!(f, "Enter"),
             BaseKey::Tab => write!(f, "Tab"),
+            BaseKey::Esc => write!(f, "Esc"),
             BaseKey::Left => write!(f, "←"),
             BaseKey::Right => write!(f, "→"),
             BaseKey::Up => write!(f, "↑"),
@@ -45,12 +50,89 @@ impl Display for Key {
     }
 }
 
+impl BaseKey {
+    // Name used for the key in the config file
+    pub fn config_name(&self) -> String {
+        match self {
+            BaseKey::None => String::new(),
+            BaseKey::Char(' ') => "Space".to_string(),
+            BaseKey::Char(c) => c.to_string(),
+            BaseKey::Enter => "Enter".to_string(),
+            BaseKey::Tab => "Tab".to_string(),
+            BaseKey::Esc => "Esc".to_string(),
+            BaseKey::Left => "Left".to_string(),
+            BaseKey::Right => "Right".to_string(),
+            BaseKey::Up => "Up".to_string(),
+            BaseKey::Down => "Down".to_string(),
+        }
+    }
+}
+
+impl Key {
+    // Name used for the key in the config file, parses back into the same key
+    pub fn config_name(&self) -> String {
+        match self {
+            Key::Plain(key) => key.config_name(),
+            Key::Ctrl(key) => format!("Ctrl+{}", key.config_name()),
+        }
+    }
+}
+
+impl FromStr for BaseKey {
+    type Err = ();
+
+    // Single characters are taken as they are, names are case insensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(match c {
+                '←' => BaseKey::Left,
+                '→' => BaseKey::Right,
+                '↑' => BaseKey::Up,
+                '↓' => BaseKey::Down,
+                c => BaseKey::Char(c),
+            });
+        }
+
+        match s.to_lowercase().as_str() {
+            "space" => Ok(BaseKey::Char(' ')),
+            "enter" | "return" => Ok(BaseKey::Enter),
+            "tab" => Ok(BaseKey::Tab),
+            "esc" | "escape" => Ok(BaseKey::Esc),
+            "left" => Ok(BaseKey::Left),
+            "right" => Ok(BaseKey::Right),
+            "up" => Ok(BaseKey::Up),
+            "down" => Ok(BaseKey::Down),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = ();
+
+    // Accepts things like "x", "Space", "Left" or "Ctrl+c"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('+') {
+            Some((modifier, key)) if modifier.eq_ignore_ascii_case("ctrl") && !key.is_empty() => {
+                Ok(Key::Ctrl(key.parse()?))
+            }
+            _ => Ok(Key::Plain(s.parse()?)),
+        }
+    }
+}
+
 impl From<KeyEvent> for Key {
     fn from(key_event: KeyEvent) -> Self {
         let base_key = match key_event.code {
+            // The kitty protocol sends shifted letters as the lowercase key with Shift held
+            KeyCode::Char(c) if key_event.modifiers.contains(KeyModifiers::SHIFT) => {
+                BaseKey::Char(c.to_ascii_uppercase())
+            }
             KeyCode::Char(c) => BaseKey::Char(c),
             KeyCode::Enter => BaseKey::Enter,
             KeyCode::Tab => BaseKey::Tab,
+            KeyCode::Esc => BaseKey::Esc,
             KeyCode::Left => BaseKey::Left,
             KeyCode::Right => BaseKey::Right,
             KeyCode::Up => BaseKey::Up,
@@ -58,9 +140,10 @@ impl From<KeyEvent> for Key {
             _ => BaseKey::None,
         };
 
-        match key_event.modifiers {
-            KeyModifiers::CONTROL => Key::Ctrl(base_key),
-            _ => Key::Plain(base_key),
+        if key_event.modifiers.contains(KeyModifiers::CONTROL) {
+            Key::Ctrl(base_key)
+        } else {
+            Key::Plain(base_key)
         }
     }
 }
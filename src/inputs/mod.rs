@@ -1,7 +1,15 @@
 pub mod events;
 pub mod keys;
 
+// What happened to a key, terminals without the kitty keyboard protocol only ever send presses
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyState {
+    Press,
+    Repeat,
+    Release,
+}
+
 pub enum InputEvent {
-    Input(keys::Key),
+    Input(keys::Key, KeyState),
     Tick,
 }
@@ -1,3 +1,7 @@
+use crossterm::{
+    event::{KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags},
+    execute,
+};
 use std::{cell::RefCell, io, rc::Rc, time::Duration};
 
 use inputs::{events::Events, InputEvent};
@@ -9,18 +13,29 @@ pub mod inputs;
 
 use app::{ui, App, AppReturn};
 
-pub fn start_ui(app: Rc<RefCell<App>>) -> Result<(), io::Error> {
+// Only controls how often the screen is redrawn, gravity and locking are timed by the game itself
+pub fn start_ui(app: Rc<RefCell<App>>, tick_rate: Duration) -> Result<(), io::Error> {
     // setup terminal with Crossterm backend
-    let stdout = io::stdout();
+    let mut stdout = io::stdout();
     crossterm::terminal::enable_raw_mode()?;
+
+    // Ask for key release events with the kitty keyboard protocol, terminals that don't know it ignore this
+    // and the app treats every press as a tap until it sees a release
+    let enhanced = execute!(
+        stdout,
+        PushKeyboardEnhancementFlags(
+            KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+        )
+    )
+    .is_ok();
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
     terminal.hide_cursor()?;
 
-    let tick_rate = Duration::from_millis(100);
-
-    let mut events = Events::new(tick_rate);
+    let events = Events::new(tick_rate);
     events.start();
 
     loop {
@@ -31,19 +46,21 @@ pub fn start_ui(app: Rc<RefCell<App>>) -> Result<(), io::Error> {
 
         // Handle inputs
         let result = match events.next().unwrap() {
-            InputEvent::Input(key) => app.do_action(key),
+            InputEvent::Input(key, state) => app.do_action(key, state),
             InputEvent::Tick => app.update_on_tick(),
         };
 
         match result {
             AppReturn::Continue => {}
-            AppReturn::UpdateSpeed => events.update_tick_rate(app.get_tick_delay()),
             AppReturn::Exit => break,
         }
     }
 
     terminal.clear()?;
     terminal.show_cursor()?;
+    if enhanced {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
     crossterm::terminal::disable_raw_mode()?;
 
     Ok(())
@@ -1,9 +1,135 @@
 // use crate::start_ui
-use std::{cell::RefCell, io, rc::Rc};
-use termtris::{app::App, start_ui};
+use clap::Parser;
+use std::{cell::RefCell, io, path::Path, process, rc::Rc, time::Duration};
+use termtris::{
+    app::{config::Config, ui::format_time, App},
+    game::{
+        mode::{GameMode, Outcome},
+        replay::Replay,
+    },
+    start_ui,
+};
+
+mod cli;
+
+use cli::{Cli, Command};
 
 fn main() -> Result<(), io::Error> {
-    let app = Rc::new(RefCell::new(App::new()));
-    start_ui(app)?;
+    let cli = Cli::parse();
+
+    // A mode skips the main menu, a replay is watched instead of playing
+    let mut replay = None;
+    let mode = match &cli.command {
+        None => None,
+        Some(Command::Marathon) => Some(GameMode::Marathon),
+        Some(Command::Sprint { lines }) => Some(GameMode::Sprint { lines: *lines }),
+        Some(Command::Ultra { seconds }) => Some(GameMode::Ultra {
+            duration: Duration::from_secs(*seconds),
+        }),
+        Some(Command::Replay { file }) => {
+            match Replay::load(file) {
+                Ok(loaded) => replay = Some(loaded),
+                Err(err) => {
+                    eprintln!("termtris: {}: {err}", file.display());
+                    process::exit(1);
+                }
+            }
+            None
+        }
+        Some(Command::Verify { file }) => process::exit(verify(file)),
+    };
+
+    let settings = match &cli.config {
+        Some(path) => Config::load(path),
+        None => Config::load_default(),
+    };
+    let mut settings = match settings {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("termtris: {err}");
+            process::exit(1);
+        }
+    };
+    if let Some(dir) = &cli.data_dir {
+        settings.data_dir = Some(dir.clone());
+    }
+
+    let mut app = App::new(cli.game_config(), settings);
+    if let Some(replay) = replay {
+        app.watch(replay);
+    } else if let Some(mode) = mode {
+        app.start_game(mode);
+    }
+
+    let app = Rc::new(RefCell::new(app));
+    start_ui(app, Duration::from_millis(cli.tick_rate))?;
     Ok(())
 }
+
+// Play a replay back through the engine and compare how it ends with what its header says, returns the exit code
+fn verify(path: &Path) -> i32 {
+    let replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("termtris: {}: {err}", path.display());
+            return 1;
+        }
+    };
+    let recorded = replay.summary;
+    let replayed = replay.play().summary();
+    let outcome = |outcome: Option<Outcome>| match outcome {
+        Some(outcome) => outcome.to_string(),
+        None => "Left".to_string(),
+    };
+
+    println!(
+        "{}, seed {}",
+        replay.config.mode,
+        replay.config.seed.unwrap_or(0)
+    );
+    println!("{:<8}{:>12}{:>12}", "", "Recorded", "Replayed");
+    let rows = [
+        (
+            "Score",
+            recorded.score.to_string(),
+            replayed.score.to_string(),
+            recorded.score == replayed.score,
+        ),
+        (
+            "Lines",
+            recorded.lines.to_string(),
+            replayed.lines.to_string(),
+            recorded.lines == replayed.lines,
+        ),
+        (
+            "Pieces",
+            recorded.pieces.to_string(),
+            replayed.pieces.to_string(),
+            recorded.pieces == replayed.pieces,
+        ),
+        (
+            "Time",
+            format_time(recorded.time),
+            format_time(replayed.time),
+            recorded.time == replayed.time,
+        ),
+        (
+            "Outcome",
+            outcome(recorded.outcome),
+            outcome(replayed.outcome),
+            recorded.outcome == replayed.outcome,
+        ),
+    ];
+    for (name, recorded, replayed, matches) in rows {
+        let mark = if matches { "" } else { "  mismatch" };
+        println!("{name:<8}{recorded:>12}{replayed:>12}{mark}");
+    }
+
+    if recorded == replayed {
+        println!("OK");
+        0
+    } else {
+        println!("FAILED, the replay doesn't play out the way its header says");
+        1
+    }
+}